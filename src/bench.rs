@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use error::{Error, Result};
+use runner::as_nanos;
+use solution::Solver;
+
+/// Where `aoc bench` reads and saves its baseline by default.
+pub const BASELINE_PATH: &str = "bench_baseline.txt";
+
+/// The stages of a solution that are timed separately.
+pub const PHASES: [&str; 3] = ["parse", "part_1", "part_2"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    /// Returns `None` if there are no samples.
+    pub fn from_samples(samples: &mut [Duration]) -> Option<Stats> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        // The two middle samples are the same one when there's an odd number.
+        let n = samples.len();
+        let median = (samples[(n - 1) / 2] + samples[n / 2]) / 2;
+        Some(Stats {
+            min: samples[0],
+            median,
+            max: samples[n - 1],
+        })
+    }
+}
+
+/// Timings for each of `PHASES`, in order.
+#[derive(Debug, PartialEq)]
+pub struct DayBench {
+    pub day: u32,
+    pub phases: [Stats; 3],
+}
+
+/// Solves `input` `iterations` times, after one untimed warm-up run.
+pub fn bench(day: u32, solver: &dyn Solver, input: &str, iterations: usize) -> Result<DayBench> {
+    solver.solve(input)?;
+
+    let mut samples = [vec![], vec![], vec![]];
+    for _ in 0..iterations {
+        let answers = solver.solve(input)?;
+        samples[0].push(answers.parse_time);
+        samples[1].push(answers.part_1.elapsed);
+        samples[2].push(answers.part_2.elapsed);
+    }
+
+    let stats = |samples: &mut Vec<Duration>| {
+        Stats::from_samples(samples)
+            .ok_or_else(|| Error::no_solution("need at least one iteration to benchmark"))
+    };
+    Ok(DayBench {
+        day,
+        phases: [
+            stats(&mut samples[0])?,
+            stats(&mut samples[1])?,
+            stats(&mut samples[2])?,
+        ],
+    })
+}
+
+/// Median time per day and phase from an earlier run, so that later runs can
+/// be checked for slowdowns.
+///
+/// The file has one `day phase median_ns` line per entry, and `#` comments.
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline(BTreeMap<(u32, String), Duration>);
+
+impl Baseline {
+    /// A missing file is an empty baseline.
+    pub fn read(path: &str) -> Result<Baseline> {
+        match std::fs::read_to_string(path) {
+            Ok(input) => Baseline::parse(&input),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Baseline::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Baseline> {
+        let mut baseline = BTreeMap::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let entry = match fields[..] {
+                [day, phase, nanos] if PHASES.contains(&phase) => day
+                    .parse()
+                    .ok()
+                    .and_then(|day| nanos.parse().ok().map(|nanos| (day, phase, nanos))),
+                _ => None,
+            };
+            let (day, phase, nanos) = entry.ok_or_else(|| Error::Parse {
+                line: i + 1,
+                column: 1,
+                message: "Expected `day phase median_ns`".to_string(),
+            })?;
+            baseline.insert((day, phase.to_string()), Duration::from_nanos(nanos));
+        }
+        Ok(Baseline(baseline))
+    }
+
+    pub fn get(&self, day: u32, phase: &str) -> Option<Duration> {
+        self.0.get(&(day, phase.to_string())).cloned()
+    }
+
+    /// Records the medians from `bench`, replacing any earlier ones for the
+    /// same day.
+    pub fn update(&mut self, bench: &DayBench) {
+        for (phase, stats) in PHASES.iter().zip(bench.phases.iter()) {
+            self.0.insert((bench.day, phase.to_string()), stats.median);
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        let mut out = String::from("# aoc bench baseline: day phase median_ns\n");
+        for (&(day, ref phase), &median) in self.0.iter() {
+            writeln!(out, "{} {} {}", day, phase, as_nanos(median)).unwrap();
+        }
+        std::fs::write(path, out).map_err(|e| Error::io(path, e))
+    }
+}
+
+/// Relative change of `now` from `then`, in percent.
+pub fn change(then: Duration, now: Duration) -> f64 {
+    let then = as_nanos(then) as f64;
+    let now = as_nanos(now) as f64;
+    if then == 0.0 {
+        0.0
+    } else {
+        (now - then) / then * 100.0
+    }
+}
+
+/// Prints min/median/max for each phase, compared against `baseline` where it
+/// has an entry.
+/// Returns whether any median is more than `threshold` percent slower than its
+/// baseline.
+pub fn print_report(bench: &DayBench, baseline: &Baseline, threshold: f64) -> bool {
+    let mut slower = false;
+    println!(
+        "day {:<4} {:>12} {:>12} {:>12} {:>12} {:>9}",
+        bench.day, "min", "median", "max", "baseline", "change"
+    );
+    for (phase, stats) in PHASES.iter().zip(bench.phases.iter()) {
+        let mut line = format!(
+            "{:<8} {:>12} {:>12} {:>12}",
+            phase,
+            format!("{:.2?}", stats.min),
+            format!("{:.2?}", stats.median),
+            format!("{:.2?}", stats.max)
+        );
+        if let Some(then) = baseline.get(bench.day, phase) {
+            let change = change(then, stats.median);
+            write!(line, " {:>12} {:>+8.1}%", format!("{:.2?}", then), change).unwrap();
+            if change > threshold {
+                slower = true;
+                line.push_str("  SLOWER");
+            }
+        }
+        println!("{}", line);
+    }
+    slower
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn test_stats() {
+        assert_eq!(Stats::from_samples(&mut []), None);
+        assert_eq!(
+            Stats::from_samples(&mut [ms(3), ms(1), ms(2)]),
+            Some(Stats {
+                min: ms(1),
+                median: ms(2),
+                max: ms(3)
+            })
+        );
+        assert_eq!(
+            Stats::from_samples(&mut [ms(4), ms(1), ms(2), ms(9)]).map(|s| s.median),
+            Some(ms(3))
+        );
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let stats = Stats {
+            min: ms(1),
+            median: ms(2),
+            max: ms(3),
+        };
+        let mut baseline = Baseline::default();
+        baseline.update(&DayBench {
+            day: 6,
+            phases: [stats, stats, stats],
+        });
+
+        let path = std::env::temp_dir().join("aoc_test_baseline.txt");
+        let path = path.to_str().unwrap();
+        baseline.write(path).unwrap();
+        assert_eq!(Baseline::read(path).unwrap(), baseline);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(baseline.get(6, "part_2"), Some(ms(2)));
+        assert_eq!(baseline.get(7, "part_2"), None);
+    }
+
+    #[test]
+    fn test_parse_baseline_errors() {
+        assert_eq!(
+            Baseline::parse("# ok\n6 part_1 100\n6 part_3 100")
+                .unwrap_err()
+                .to_string(),
+            "parse error at line 3, column 1: Expected `day phase median_ns`"
+        );
+    }
+
+    #[test]
+    fn test_change() {
+        assert_eq!(change(ms(100), ms(150)), 50.0);
+        assert_eq!(change(ms(100), ms(50)), -50.0);
+    }
+
+    #[test]
+    fn test_bench() {
+        let registry = ::registry();
+        let bench = bench(1, registry.get(1).unwrap(), "+1\n-1", 3).unwrap();
+        assert_eq!(bench.day, 1);
+        for stats in bench.phases.iter() {
+            assert!(stats.min <= stats.median && stats.median <= stats.max);
+        }
+    }
+}
@@ -1,19 +1,35 @@
-pub fn run(path: &str) {
-    let freqs = parse_input(path);
+use error::{Error, Result};
+use parse;
+use solution::Solution;
 
-    let part_1_solution = part_1(&freqs);
-    println!("Day 1, part 1: {}", part_1_solution);
+pub struct Day1;
 
-    let part_2_solution = part_2(&freqs);
-    println!("Day 1, part 2: {}", part_2_solution);
-}
+impl Solution for Day1 {
+    type Input = Vec<i32>;
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn parse(input: &str) -> Result<Vec<i32>> {
+        parse_input(input)
+    }
 
-pub fn parse_input(path: &str) -> Vec<i32> {
-    let data = std::fs::read_to_string(path).expect("Couldn't read data file");
+    fn part_1(freqs: &Vec<i32>) -> Result<i32> {
+        Ok(part_1(freqs))
+    }
 
-    data.lines()
-        .map(|f| f.parse().expect("Bad frequency"))
-        .collect()
+    fn part_2(freqs: &Vec<i32>) -> Result<i32> {
+        part_2(freqs).ok_or_else(|| {
+            if freqs.is_empty() {
+                Error::no_solution("no frequency changes")
+            } else {
+                Error::no_solution("the frequency never repeats")
+            }
+        })
+    }
+}
+
+pub fn parse_input(data: &str) -> Result<Vec<i32>> {
+    parse::lines_of(data, parse::signed())
 }
 
 pub fn part_1(frequencies: &[i32]) -> i32 {
@@ -30,25 +46,59 @@ fn day_1_test() {
 
 use std::collections::HashSet;
 
-pub fn part_2(frequencies: &[i32]) -> i32 {
+/// The first frequency reached twice, or `None` if none ever is.
+pub fn part_2(frequencies: &[i32]) -> Option<i32> {
     let mut seen_frequencies = HashSet::new();
+    let mut firsts = vec![];
     let mut current_freq = 0;
-    loop {
-        for f in frequencies {
-            seen_frequencies.insert(current_freq);
-            current_freq += f;
-            if seen_frequencies.contains(&current_freq) {
-                return current_freq;
-            }
+    for f in frequencies {
+        seen_frequencies.insert(current_freq);
+        firsts.push(i64::from(current_freq));
+        current_freq += f;
+        if seen_frequencies.contains(&current_freq) {
+            return Some(current_freq);
         }
     }
+
+    // Every later pass goes through the first pass's frequencies shifted by
+    // `drift` each time, so one of them comes round again in steps of
+    // `drift`. The first repeat is the soonest to land on another of them:
+    // the fewest steps, then the earliest in the pass. With no drift, the
+    // second pass would have started with a repeat.
+    let drift = i64::from(current_freq);
+    if drift == 0 {
+        return None;
+    }
+    let (sign, drift) = if drift > 0 { (1, drift) } else { (-1, -drift) };
+    let mut firsts: Vec<(i64, usize)> = firsts
+        .into_iter()
+        .enumerate()
+        .map(|(i, freq)| (sign * freq, i))
+        .collect();
+    firsts.sort_unstable_by_key(|&(freq, _)| (freq.rem_euclid(drift), freq));
+    firsts
+        .windows(2)
+        .filter(|pair| pair[0].0.rem_euclid(drift) == pair[1].0.rem_euclid(drift))
+        .min_by_key(|pair| ((pair[1].0 - pair[0].0) / drift, pair[0].1))
+        .map(|pair| (sign * pair[1].0) as i32)
 }
 
 #[test]
 fn day_1_part_2_test() {
-    assert_eq!(part_2(&[1, -2, 3, 1]), 2);
-    assert_eq!(part_2(&[1, -1]), 0);
-    assert_eq!(part_2(&[3, 3, 4, -2, -4]), 10);
-    assert_eq!(part_2(&[-6, 3, 8, 5, -6]), 5);
-    assert_eq!(part_2(&[7, 7, -2, -7, -4]), 14);
+    assert_eq!(part_2(&[1, -2, 3, 1]), Some(2));
+    assert_eq!(part_2(&[1, -1]), Some(0));
+    assert_eq!(part_2(&[3, 3, 4, -2, -4]), Some(10));
+    assert_eq!(part_2(&[-6, 3, 8, 5, -6]), Some(5));
+    assert_eq!(part_2(&[7, 7, -2, -7, -4]), Some(14));
+    assert_eq!(part_2(&[]), None);
+    assert_eq!(part_2(&[1]), None);
+    assert_eq!(part_2(&[5, -2]), None);
+    assert_eq!(part_2(&[-3, 1]), None);
+    assert_eq!(part_2(&[-3, 1, 5, -2]), Some(-2));
+    assert_eq!(part_2(&[-3, 1, -1]), Some(-3));
+    assert_eq!(part_2(&[1000, -999]), Some(1000));
+    assert_eq!(
+        Day1::part_2(&vec![1]).unwrap_err().to_string(),
+        "no solution: the frequency never repeats"
+    );
 }
@@ -1,146 +1,344 @@
-// use std::collections::HashMap;
 use std::collections::HashSet;
-use std::io::Write;
+use std::convert::TryFrom;
+use std::fmt;
 
-use combine::{
-    easy::Stream,
-    parser::char::{newline, spaces, string},
-    Parser,
-};
+use combine::{parser::char::spaces, Parser};
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read input file");
+use error::{Error, Result};
+use geom::{Point, Rect, Vector, MAX_CELLS};
+use ocr;
+use parse::{self, field, signed, tag, Stream};
+use render::{self, Color, Image};
+use solution::Solution;
 
-    let mut map = Map::parser()
-        .easy_parse(&input)
-        .expect("Couldn't parse sample points")
-        .0;
+pub struct Day10;
 
-    println!("Initially:");
-    map.draw();
+impl Solution for Day10 {
+    type Input = Map;
+    type Part1 = String;
+    type Part2 = i64;
 
-    for i in 0.. {
-        map.step();
-        println!("After {} steps:", i + 1);
-        map.draw();
-        std::io::stdin().read_line(&mut String::new());
+    fn parse(input: &str) -> Result<Map> {
+        parse::parse_all(input, Map::parser())
     }
+
+    fn part_1(map: &Map) -> Result<String> {
+        let time = message_time(map)?;
+        ocr::read(&map.frame_at(time)?.to_string())
+    }
+
+    fn part_2(map: &Map) -> Result<i64> {
+        message_time(map)
+    }
+
+    fn render(map: &Map) -> Result<Option<Image>> {
+        let time = message_time(map)?;
+        Ok(Some(enlarge(&map.frame_at(time)?.image())))
+    }
+
+    fn animate(map: &Map, steps: i64) -> Result<Option<render::Frames>> {
+        let time = message_time(map)?;
+        let (first, last) = match (time.checked_sub(steps), time.checked_add(steps)) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(Error::no_solution("the window goes past the largest time")),
+        };
+        let frames = map.frames(first, last)?;
+        Ok(Some(Box::new(frames.map(|f| enlarge(&f.image())))))
+    }
+}
+
+/// Scales a picture of the sky up to about 256 pixels across, since the
+/// message is tiny.
+fn enlarge(image: &Image) -> Image {
+    image.scaled((256 / image.width.max(image.height).max(1)).max(1))
+}
+
+fn message_time(map: &Map) -> Result<i64> {
+    map.converge_time()
+        .ok_or_else(|| Error::no_solution("there are no points of light"))
 }
 
-#[derive(Debug)]
-struct Map(Vec<Point>);
+/// The error for lights spread over more than `MAX_CELLS` squares.
+fn too_spread_out(bounds: &Rect<i64>) -> Error {
+    Error::no_solution(format!(
+        "the lights never get close enough to draw: {}x{} squares is too many",
+        bounds.max.x.saturating_sub(bounds.min.x),
+        bounds.max.y.saturating_sub(bounds.min.y)
+    ))
+}
+
+/// Points of light in the sky, as they were at time 0.
+#[derive(Debug, Clone)]
+pub struct Map(Vec<Light>);
 impl Map {
-    fn parser<'a>() -> impl Parser<Input = Stream<&'a str>, Output = Map> {
-        combine::sep_end_by::<Vec<_>, _, _>(Point::parser(), newline()).map(|points| Map(points))
+    fn parser<'a>() -> impl Parser<Input = Stream<'a>, Output = Map> {
+        parse::lines(Light::parser()).map(Map)
+    }
+
+    /// Moves every light on by one second.
+    pub fn step(&mut self) {
+        self.advance(1);
+    }
+
+    /// Moves every light back by one second.
+    pub fn step_back(&mut self) {
+        self.advance(-1);
     }
 
-    fn step(&mut self) {
-        for point in self.0.iter_mut() {
-            point.pos.x += point.vel.vx;
-            point.pos.y += point.vel.vy;
+    /// Moves every light on by `seconds`, or back if it's negative.
+    pub fn advance(&mut self, seconds: i64) {
+        for light in self.0.iter_mut() {
+            light.pos += light.vel * seconds;
         }
     }
 
-    fn draw(&self) {
-        let map: HashSet<&Position> = self.0.iter().map(|point| (&point.pos)).collect();
+    /// Where every light is at `time`, or `None` if one of them is past the
+    /// largest coordinate by then.
+    fn checked_positions_at(&self, time: i64) -> Option<Vec<Point<i64>>> {
+        self.0
+            .iter()
+            .map(|light| {
+                let moved = Vector::new(
+                    light.vel.x.checked_mul(time)?,
+                    light.vel.y.checked_mul(time)?,
+                );
+                light.pos.checked_add(moved)
+            })
+            .collect()
+    }
+
+    fn positions_at<'a>(&'a self, time: i64) -> impl Iterator<Item = Point<i64>> + 'a {
+        self.0.iter().map(move |light| light.pos + light.vel * time)
+    }
+
+    pub fn bounds_at(&self, time: i64) -> Option<Rect<i64>> {
+        Rect::bounding(&self.positions_at(time).collect::<Vec<_>>())
+    }
+
+    /// Half the perimeter of the bounding box at `time`.
+    ///
+    /// Each side of the box moves at the speed of whichever light is
+    /// furthest out on that side, and that light only changes as others
+    /// overtake it, so this is convex in `time`. (The area isn't, which is
+    /// why it's not used.)
+    fn spread_at(&self, time: i64) -> i64 {
+        self.bounds_at(time)
+            .map(|bounds| bounds.width() + bounds.height())
+            .unwrap_or(0)
+    }
+
+    /// The first time from 0 on at which the lights are closest together,
+    /// which is when they spell out the message.
+    pub fn converge_time(&self) -> Option<i64> {
+        if self.0.is_empty() {
+            return None;
+        }
 
-        std::io::stdout().write(b"==========\n");
-        for y in 0..20 {
-            for x in 0..20 {
-                if map.contains(&Position::new(x, y)) {
-                    std::io::stdout().write(b"#");
-                } else {
-                    std::io::stdout().write(b".");
-                }
+        // The spread is convex, so it's falling until the first `t` where
+        // `spread_at(t + 1) >= spread_at(t)`. Find an upper bound for that by
+        // doubling, then binary search below it.
+        let falling = |t| self.spread_at(t + 1) < self.spread_at(t);
+        let mut high = 1;
+        while falling(high) {
+            high *= 2;
+        }
+        let mut low = 0;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if falling(mid) {
+                low = mid + 1;
+            } else {
+                high = mid;
             }
-            std::io::stdout().write(b"\n");
+        }
+        Some(low)
+    }
+
+    /// The sky at `time`, cropped to the lights, or an error if they're too
+    /// spread out to draw.
+    pub fn frame_at(&self, time: i64) -> Result<Frame> {
+        let bounds = self
+            .bounds_at(time)
+            .unwrap_or_else(|| Rect::new(Point::origin(), Point::origin()));
+        self.frame_in(time, &bounds)
+    }
+
+    /// The part of the sky inside `bounds` at `time`, or an error if that's
+    /// more than `MAX_CELLS` squares.
+    fn frame_in(&self, time: i64, bounds: &Rect<i64>) -> Result<Frame> {
+        bounds
+            .checked_cells(MAX_CELLS)
+            .ok_or_else(|| too_spread_out(bounds))?;
+        Ok(self.draw(time, bounds))
+    }
+
+    /// Like `frame_in`, for `bounds` that have already been checked.
+    fn draw(&self, time: i64, bounds: &Rect<i64>) -> Frame {
+        let lit: HashSet<Point<i64>> = self.positions_at(time).collect();
+        Frame {
+            width: bounds.width() as usize,
+            height: bounds.height() as usize,
+            cells: bounds.points().map(|p| lit.contains(&p)).collect(),
         }
     }
+
+    /// The sky at each second from `first` to `last`, all cropped to the same
+    /// box so that the lights line up from one frame to the next. Either time
+    /// may be negative, to look back from where the lights started. Each
+    /// frame is drawn as it's asked for.
+    ///
+    /// It's an error if the lights go past the largest coordinate in that
+    /// time, or if the box is more than `MAX_CELLS` squares.
+    pub fn frames(&self, first: i64, last: i64) -> Result<Frames> {
+        let count = if last < first {
+            0
+        } else {
+            last.checked_sub(first)
+                .and_then(|steps| usize::try_from(steps).ok())
+                .and_then(|steps| steps.checked_add(1))
+                .ok_or_else(|| Error::no_solution("too many frames to draw"))?
+        };
+
+        // Every light moves in a straight line, so the box around all of the
+        // frames is the box around the first and last.
+        let bounds_at = |time| {
+            self.checked_positions_at(time)
+                .and_then(|positions| Rect::checked_bounding(&positions))
+                .ok_or_else(|| Error::no_solution("the lights go too far out to draw"))
+        };
+        let bounds = if count == 0 || self.0.is_empty() {
+            Rect::new(Point::origin(), Point::origin())
+        } else {
+            bounds_at(first)?.union(&bounds_at(last)?)
+        };
+        bounds
+            .checked_cells(MAX_CELLS)
+            .ok_or_else(|| too_spread_out(&bounds))?;
+
+        let mut sky = self.clone();
+        if count > 0 {
+            sky.advance(first);
+        }
+        Ok(Frames {
+            sky,
+            bounds,
+            remaining: count,
+        })
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Point {
-    pos: Position,
-    vel: Velocity,
+/// The iterator returned by `Map::frames`.
+pub struct Frames {
+    /// The sky at the time of the next frame.
+    sky: Map,
+    bounds: Rect<i64>,
+    remaining: usize,
 }
 
-impl Point {
-    fn new(x: i32, y: i32, vx: i32, vy: i32) -> Point {
-        Point {
-            pos: Position { x, y },
-            vel: Velocity { vx, vy },
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let frame = self.sky.draw(0, &self.bounds);
+        self.remaining -= 1;
+        // Stepping past the last frame could go past the largest coordinate.
+        if self.remaining > 0 {
+            self.sky.step();
         }
+        Some(frame)
     }
 
-    fn parser<'a>() -> impl Parser<Input = Stream<&'a str>, Output = Point> {
-        struct_parser!{
-            Point {
-                pos: Position::parser(),
-                _: spaces(),
-                vel: Velocity::parser(),
-            }
-        }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Position {
-    x: i32,
-    y: i32,
+impl ExactSizeIterator for Frames {}
+
+/// A grid of lit and unlit cells, row by row.
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<bool>,
+}
+
+impl Frame {
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x]
+    }
+
+    /// The frame as one pixel per cell, with a dark border a cell wide.
+    pub fn image(&self) -> Image {
+        let night = Color::rgb(10, 10, 40);
+        Image::from_fn(self.width + 2, self.height + 2, |x, y| {
+            if x > 0 && y > 0 && self.is_lit(x - 1, y - 1) {
+                Color::WHITE
+            } else {
+                night
+            }
+        })
+    }
 }
-impl Position {
-    fn new(x: i32, y: i32) -> Position {
-        Position { x, y }
-    }
-
-    fn parser<'a>() -> impl Parser<Input = Stream<&'a str>, Output = Position> {
-        struct_parser!{
-            Position {
-                _: string("position=<").skip(spaces()),
-                x: parse_i32(),
-                _: string(",").skip(spaces()),
-                y: parse_i32(),
-                _: string(">"),
+
+/// Draws lit cells as `#` and unlit ones as `.`. Every row, including the
+/// first, starts on a new line, so the frame lines up after a label.
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height {
+            writeln!(f)?;
+            for x in 0..self.width {
+                write!(f, "{}", if self.is_lit(x, y) { '#' } else { '.' })?;
             }
         }
+        Ok(())
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Velocity {
-    vx: i32,
-    vy: i32,
+/// A point of light in the sky.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Light {
+    pos: Point<i64>,
+    vel: Vector<i64>,
 }
-impl Velocity {
-    fn new(vx: i32, vy: i32) -> Velocity {
-        Velocity { vx, vy }
-    }
-
-    fn parser<'a>() -> impl Parser<Input = Stream<&'a str>, Output = Velocity> {
-        struct_parser!{
-            Velocity {
-                _: string("velocity=<").skip(spaces()),
-                vx: parse_i32(),
-                _: string(",").skip(spaces()),
-                vy: parse_i32(),
-                _: string(">"),
+
+impl Light {
+    fn parser<'a>() -> impl Parser<Input = Stream<'a>, Output = Light> {
+        struct_parser! {
+            Light {
+                pos: position(),
+                _: spaces(),
+                vel: velocity(),
             }
         }
     }
 }
 
-fn parse_u32<'a>() -> impl Parser<Input = Stream<&'a str>, Output = u32> {
-    combine::from_str(combine::parser::range::take_while1(|c: char| {
-        c.is_digit(10)
-    }))
+fn position<'a>() -> impl Parser<Input = Stream<'a>, Output = Point<i64>> {
+    struct_parser! {
+        Point {
+            _: tag("position=<"),
+            x: field(signed()),
+            _: tag(","),
+            y: field(signed()),
+            _: tag(">"),
+        }
+    }
 }
 
-fn parse_i32<'a>() -> impl Parser<Input = Stream<&'a str>, Output = i32> {
-    (
-        combine::parser::choice::optional(combine::parser::char::char('-')),
-        parse_u32(),
-    )
-        .map(|t| t.1 as i32 * (if t.0.is_some() { -1 } else { 1 }))
+fn velocity<'a>() -> impl Parser<Input = Stream<'a>, Output = Vector<i64>> {
+    struct_parser! {
+        Vector {
+            _: tag("velocity=<"),
+            x: field(signed()),
+            _: tag(","),
+            y: field(signed()),
+            _: tag(">"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -184,16 +382,129 @@ position=<-3,  6> velocity=< 2, -1>"
     #[test]
     fn test_position_parser() {
         assert_eq!(
-            Position::parser().easy_parse("position=< -10, 1>"),
-            Ok((Position::new(-10, 1), ""))
+            position().easy_parse("position=< -10, 1>"),
+            Ok((Point::new(-10, 1), ""))
         );
     }
 
     #[test]
     fn test_velocity_parser() {
         assert_eq!(
-            Velocity::parser().easy_parse("velocity=<1,-10>"),
-            Ok((Velocity::new(1, -10), ""))
+            velocity().easy_parse("velocity=<1,-10>"),
+            Ok((Vector::new(1, -10), ""))
+        );
+    }
+
+    #[test]
+    fn test_map_parser() {
+        let (map, rest) = Map::parser().easy_parse(sample_points()).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(map.0.len(), 31);
+        assert_eq!(map.0[2].pos, Point::new(3, -2));
+        assert_eq!(map.0[2].vel, Vector::new(-1, 1));
+    }
+
+    #[test]
+    fn test_converge_time() {
+        let mut map = Day10::parse(sample_points()).unwrap();
+        assert_eq!(map.converge_time(), Some(3));
+        assert_eq!(
+            map.frame_at(3).unwrap().to_string(),
+            [
+                "",
+                "#...#..###",
+                "#...#...#.",
+                "#...#...#.",
+                "#####...#.",
+                "#...#...#.",
+                "#...#...#.",
+                "#...#...#.",
+                "#...#..###",
+            ]
+            .join("\n")
         );
+
+        let image = map.frame_at(3).unwrap().image();
+        assert_eq!((image.width, image.height), (12, 10));
+        assert_eq!(image.get(1, 1), Some(Color::WHITE));
+        assert_eq!(image.get(2, 1), Some(Color::rgb(10, 10, 40)));
+
+        // Starting later, the message has already been and gone.
+        for _ in 0..5 {
+            map.step();
+        }
+        assert_eq!(map.converge_time(), Some(0));
+        assert_eq!(Map(vec![]).converge_time(), None);
+    }
+
+    #[test]
+    fn test_too_spread_out() {
+        let map =
+            Day10::parse("position=<0,0> velocity=<0,0>\nposition=<100000,100000> velocity=<0,0>")
+                .unwrap();
+        assert_eq!(map.converge_time(), Some(0));
+        assert_eq!(
+            Day10::part_1(&map).unwrap_err().to_string(),
+            "no solution: the lights never get close enough to draw: 100001x100001 squares is too many"
+        );
+        assert!(Day10::render(&map).is_err());
+        assert!(map.frames(0, 1).is_err());
+        assert_eq!(Day10::part_2(&map).unwrap(), 0);
+
+        // The lights meet, but the window around them is too wide to draw.
+        let map =
+            Day10::parse("position=<-3000,0> velocity=<1,0>\nposition=<3000,0> velocity=<-1,0>")
+                .unwrap();
+        assert_eq!(map.converge_time(), Some(3000));
+        assert!(Day10::render(&map).is_ok());
+        assert!(Day10::animate(&map, 10).is_ok());
+        assert_eq!(
+            Day10::animate(&map, 20_000_000).err().unwrap().to_string(),
+            "no solution: the lights never get close enough to draw: 40000001x1 squares is too many"
+        );
+        assert_eq!(
+            Day10::animate(&map, i64::MAX).err().unwrap().to_string(),
+            "no solution: the window goes past the largest time"
+        );
+        assert_eq!(
+            map.frames(0, i64::MAX).err().unwrap().to_string(),
+            "no solution: the lights go too far out to draw"
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        let map = Day10::parse(sample_points()).unwrap();
+        let frames: Vec<Frame> = map.frames(2, 4).unwrap().collect();
+        assert_eq!(frames.len(), 3);
+        // Every frame is cropped to the box around all three.
+        let bounds = map.bounds_at(2).unwrap().union(&map.bounds_at(4).unwrap());
+        for frame in frames.iter() {
+            assert_eq!(frame.width, bounds.width() as usize);
+            assert_eq!(frame.height, bounds.height() as usize);
+        }
+        let message = map.bounds_at(3).unwrap().min - bounds.min;
+        let (x, y) = (message.x as usize, message.y as usize);
+        assert!(frames[1].is_lit(x, y));
+        assert!(!frames[1].is_lit(x + 1, y));
+
+        // Stepping back undoes stepping on, even past time 0.
+        let mut sky = map.clone();
+        sky.step_back();
+        sky.step_back();
+        assert_eq!(sky.frame_at(0).unwrap(), map.frame_at(-2).unwrap());
+        assert_eq!(
+            map.frames(-2, -2).unwrap().next().unwrap(),
+            map.frame_at(-2).unwrap()
+        );
+        assert_eq!(map.frames(2, 4).unwrap().len(), 3);
+        assert_eq!(map.frames(4, 2).unwrap().len(), 0);
+        sky.step();
+        sky.advance(4);
+        assert_eq!(sky.frame_at(0).unwrap(), map.frame_at(3).unwrap());
+
+        let frames = Day10::animate(&map, 1).unwrap().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames.count(), 3);
     }
 }
@@ -1,12 +1,36 @@
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read data file.");
-    let boxes: Vec<&str> = input.lines().collect();
+// Written before these lints existed.
+#![allow(clippy::useless_vec)]
 
-    let csum = part_1::run(&boxes[..]);
-    println!("Day 2, part 1: {}", csum);
+use combine::Parser;
 
-    let letters = part_2::run(&boxes[..]);
-    println!("Day 2, part 2: {}", letters);
+use error::{Error, Result};
+use parse;
+use solution::Solution;
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<String>;
+    type Part1 = u32;
+    type Part2 = String;
+
+    fn parse(input: &str) -> Result<Vec<String>> {
+        parse::lines_of(input, parse::word().map(String::from))
+    }
+
+    fn part_1(boxes: &Vec<String>) -> Result<u32> {
+        Ok(part_1::run(&as_strs(boxes)))
+    }
+
+    fn part_2(boxes: &Vec<String>) -> Result<String> {
+        part_2::run(&as_strs(boxes)).ok_or_else(|| {
+            Error::no_solution("no two box IDs differ by exactly one character")
+        })
+    }
+}
+
+fn as_strs(boxes: &[String]) -> Vec<&str> {
+    boxes.iter().map(|b| b.as_str()).collect()
 }
 
 mod part_1 {
@@ -75,9 +99,8 @@ mod part_1 {
 }
 
 mod part_2 {
-    pub fn run(boxes: &[&str]) -> String {
-        let (b1, b2) = find_matching_boxes(boxes).expect("No boxes matched");
-        common_letters(b1, b2)
+    pub fn run(boxes: &[&str]) -> Option<String> {
+        find_matching_boxes(boxes).map(|(b1, b2)| common_letters(b1, b2))
     }
 
     fn find_matching_boxes<'a>(boxes: &[&'a str]) -> Option<(&'a str, &'a str)> {
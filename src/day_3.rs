@@ -1,155 +1,671 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
+use combine::Parser;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read data file.");
+use error::{Error, Result};
+use geom::{Point, Rect, MAX_CELLS};
+use parse::{self, field, located, tag, unsigned};
+use render::{self, Image};
+use solution::Solution;
 
-    let (_, claims) = parse_claims(&input).expect("Couldn't parse claims");
+pub struct Day3;
 
-    let soln = double_claimed_squares(&claimed_square_counts(&claims)).len();
-    println!("Day 3, part 1: {}", soln);
+impl Solution for Day3 {
+    type Input = Vec<Claim>;
+    type Part1 = usize;
+    type Part2 = u32;
 
-    let part_2_soln = part_2(&claims);
-    println!("Day 3, part 2: {:?}", part_2_soln);
+    fn parse(input: &str) -> Result<Vec<Claim>> {
+        parse::lines_of(input, located(claim()))?
+            .into_iter()
+            .map(|claim| match claim.value {
+                Ok(value) => Ok(value),
+                Err(ref message) => Err(claim.error(input, message.as_str())),
+            })
+            .collect()
+    }
+
+    fn part_1(claims: &Vec<Claim>) -> Result<usize> {
+        Ok(double_claimed_squares(claims) as usize)
+    }
+
+    fn part_2(claims: &Vec<Claim>) -> Result<u32> {
+        part_2(claims)
+            .map(|claim| claim.id)
+            .ok_or_else(|| Error::no_solution("every claim overlaps another"))
+    }
+
+    fn render(claims: &Vec<Claim>) -> Result<Option<Image>> {
+        render_claims(claims).map(Some)
+    }
+
+    fn report(claims: &Vec<Claim>) -> Result<Option<String>> {
+        Ok(Some(OverlapGraph::new(claims).report()))
+    }
 }
 
 #[derive(Debug, PartialEq)]
-struct Claim {
-    id: u32,
-    rectangle: Rectangle,
+pub struct Claim {
+    pub id: u32,
+    pub rectangle: Rect,
 }
 
-#[derive(Debug, PartialEq)]
-struct Rectangle {
-    left: u32,
-    top: u32,
-    width: u32,
-    height: u32,
-}
-
-impl Rectangle {
-    fn claimed_squares(&self) -> HashSet<Square> {
-        let mut squares = HashSet::new();
-        for x in self.left..(self.left + self.width) {
-            for y in self.top..(self.top + self.height) {
-                squares.insert(Square { x, y });
+type Square = Point;
+
+/// The number of squares in `rect`, which can be more than fit in its
+/// coordinates.
+fn squares_in(rect: &Rect) -> u64 {
+    rect.width() as u64 * rect.height() as u64
+}
+
+/// The error for a stretch of fabric with more than `MAX_CELLS` squares.
+fn too_big(rect: &Rect) -> Error {
+    Error::no_solution(format!(
+        "the fabric is too big: {}x{} squares is too many",
+        rect.width(),
+        rect.height()
+    ))
+}
+
+/// How many claims cover each square of the fabric.
+#[derive(Debug)]
+struct ClaimCounts {
+    /// The smallest rectangle holding all of the claims.
+    bounds: Rect,
+    /// Row by row across `bounds`.
+    counts: Vec<u32>,
+}
+
+impl ClaimCounts {
+    /// Counts the claims with a 2D difference array, so it takes one number
+    /// per square of the fabric, however many claims there are. Each claim
+    /// adds one at its top left corner and takes it away again just past its
+    /// right and bottom edges. Summing along each row and then down each
+    /// column leaves the count for every square.
+    ///
+    /// Fails if the claims are spread over too much fabric to count.
+    fn new(claims: &[Claim]) -> Result<ClaimCounts> {
+        let bounds = claims.iter().fold(
+            Rect::new(Point::origin(), Point::origin()),
+            |bounds, claim| bounds.union(&claim.rectangle),
+        );
+        bounds
+            .checked_cells(MAX_CELLS)
+            .ok_or_else(|| too_big(&bounds))?;
+        let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+        // An extra column and row for the claims that reach the far edges.
+        let stride = width + 1;
+        let index =
+            |p: Point| (p.y - bounds.min.y) as usize * stride + (p.x - bounds.min.x) as usize;
+
+        let mut diffs = vec![0i32; stride * (height + 1)];
+        for claim in claims.iter().filter(|c| !c.rectangle.is_empty()) {
+            let Rect { min, max } = claim.rectangle;
+            diffs[index(min)] += 1;
+            diffs[index(Point::new(max.x, min.y))] -= 1;
+            diffs[index(Point::new(min.x, max.y))] -= 1;
+            diffs[index(max)] += 1;
+        }
+        for row in diffs.chunks_mut(stride) {
+            for x in 1..stride {
+                row[x] += row[x - 1];
+            }
+        }
+        for y in 1..height + 1 {
+            let (above, below) = diffs.split_at_mut(y * stride);
+            for (count, above) in below.iter_mut().zip(&above[(y - 1) * stride..]) {
+                *count += above;
             }
         }
-        squares
+
+        Ok(ClaimCounts {
+            bounds,
+            counts: diffs
+                .chunks(stride)
+                .take(height)
+                .flat_map(|row| row[..width].iter().map(|&count| count as u32))
+                .collect(),
+        })
     }
 
-    fn overlaps(&self, other: &Rectangle) -> bool {
-        if self.left > other.left + other.width - 1 || other.left > self.left + self.width - 1 {
-            return false;
-        } else if self.top > other.top + other.height - 1 || other.top > self.top + self.height - 1
-        {
-            return false;
+    fn get(&self, square: &Square) -> u32 {
+        if self.bounds.contains(square) {
+            let offset = *square - self.bounds.min;
+            self.counts[offset.y as usize * self.bounds.width() as usize + offset.x as usize]
         } else {
-            return true;
+            0
         }
     }
+
+    fn max(&self) -> u32 {
+        self.counts.iter().cloned().max().unwrap_or(0)
+    }
+}
+
+/// The fabric with all of the claims on it, for asking which claims cover a
+/// square and how thickly any window of it is claimed.
+#[derive(Debug)]
+pub struct Fabric {
+    ids: Vec<u32>,
+    index: RectIndex,
+    counts: ClaimCounts,
+    max_claims: u32,
+    /// The summed-area tables built so far, by `k`.
+    at_least: RefCell<BTreeMap<u32, Vec<u32>>>,
 }
 
-fn claimed_square_counts(claims: &[Claim]) -> HashMap<Square, u32> {
-    let mut square_counts = HashMap::new();
-    for claim in claims {
-        for square in claim.rectangle.claimed_squares() {
-            square_counts
-                .entry(square)
-                .and_modify(|c| *c += 1)
-                .or_insert(1);
+impl Fabric {
+    /// Fails if the claims are spread over too much fabric to count.
+    pub fn new(claims: &[Claim]) -> Result<Fabric> {
+        let rects: Vec<Rect> = claims.iter().map(|c| c.rectangle).collect();
+        let counts = ClaimCounts::new(claims)?;
+        Ok(Fabric {
+            ids: claims.iter().map(|c| c.id).collect(),
+            index: RectIndex::new(&rects),
+            max_claims: counts.max(),
+            counts,
+            at_least: RefCell::new(BTreeMap::new()),
+        })
+    }
+
+    /// The smallest rectangle holding all of the claims. Every square outside
+    /// it is unclaimed.
+    pub fn bounds(&self) -> Rect {
+        self.counts.bounds
+    }
+
+    /// The most claims on any one square.
+    pub fn max_claims(&self) -> u32 {
+        self.max_claims
+    }
+
+    /// The number of claims covering `square`.
+    pub fn count(&self, square: &Square) -> u32 {
+        self.counts.get(square)
+    }
+
+    /// The ids of the claims covering `square`, in order.
+    pub fn claims_at(&self, square: &Square) -> Vec<u32> {
+        // A square on the last row or column can't be claimed.
+        Rect::checked_with_size(*square, 1, 1).map_or_else(Vec::new, |square| {
+            self.index
+                .overlapping(&square)
+                .into_iter()
+                .map(|i| self.ids[i])
+                .collect()
+        })
+    }
+
+    /// The summed-area table of the squares claimed at least `k` times, for
+    /// `k` from 1 to `max_claims`. Entry `(x, y)` is the number of them above
+    /// and left of `(x, y)` from the top left of `bounds`, so there's an
+    /// extra row and column.
+    fn summed_at_least(&self, k: u32) -> Vec<u32> {
+        let bounds = self.counts.bounds;
+        let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+        let stride = width + 1;
+        let mut table = vec![0; stride * (height + 1)];
+        for (y, row) in self.counts.counts.chunks(width).enumerate() {
+            let mut row_sum = 0;
+            for (x, &count) in row.iter().enumerate() {
+                if count >= k {
+                    row_sum += 1;
+                }
+                table[(y + 1) * stride + x + 1] = table[y * stride + x + 1] + row_sum;
+            }
+        }
+        table
+    }
+
+    /// The number of squares in `window` claimed at least `k` times. The
+    /// table for each `k` is built the first time it's asked for, and every
+    /// window after that takes four lookups.
+    pub fn claimed_at_least(&self, window: &Rect, k: u32) -> usize {
+        if k == 0 {
+            return squares_in(window) as usize;
         }
+        let bounds = self.counts.bounds;
+        let window = match window.intersection(&bounds) {
+            Some(window) if k <= self.max_claims => window,
+            _ => return 0,
+        };
+        let mut tables = self.at_least.borrow_mut();
+        let table = tables.entry(k).or_insert_with(|| self.summed_at_least(k));
+        let stride = bounds.width() as usize + 1;
+        let sum_to = |p: Point| {
+            let offset = p - bounds.min;
+            table[offset.y as usize * stride + offset.x as usize]
+        };
+        let (min, max) = (window.min, window.max);
+        (sum_to(max) + sum_to(min)
+            - sum_to(Point::new(min.x, max.y))
+            - sum_to(Point::new(max.x, min.y))) as usize
     }
-    square_counts
+
+    /// The number of squares in `window` claimed exactly `k` times, for each
+    /// `k` from 0 to `max_claims`.
+    pub fn histogram(&self, window: &Rect) -> Vec<usize> {
+        let at_least: Vec<usize> = (0..self.max_claims + 2)
+            .map(|k| self.claimed_at_least(window, k))
+            .collect();
+        at_least.windows(2).map(|w| w[0] - w[1]).collect()
+    }
+
+    /// The number of squares claimed more than once.
+    pub fn double_claimed_squares(&self) -> usize {
+        self.histogram(&self.bounds()).iter().skip(2).sum()
+    }
+}
+
+/// How much of a line across the fabric is covered by the claims it crosses:
+/// a segment tree over the stretches between the claims' top and bottom
+/// edges.
+struct Coverage {
+    /// The claims' top and bottom edges, in order and without repeats.
+    edges: Vec<i32>,
+    /// For each node, the claims covering all of its stretch that aren't
+    /// counted further down, and how much of it is covered at least once
+    /// and at least twice.
+    nodes: Vec<(u32, u64, u64)>,
 }
 
-fn double_claimed_squares(square_counts: &HashMap<Square, u32>) -> HashSet<&Square> {
-    square_counts
+impl Coverage {
+    fn new(mut edges: Vec<i32>) -> Coverage {
+        edges.sort_unstable();
+        edges.dedup();
+        let nodes = vec![(0, 0, 0); 4 * edges.len()];
+        Coverage { edges, nodes }
+    }
+
+    /// Adds `delta` claims covering the line from `top` to `bottom`.
+    fn add(&mut self, top: i32, bottom: i32, delta: i32) {
+        if self.edges.len() > 1 {
+            let last = self.edges.len() - 1;
+            self.update(1, 0, last, (top, bottom), delta);
+        }
+    }
+
+    /// The length of the line covered at least twice.
+    fn twice(&self) -> u64 {
+        self.nodes.get(1).map_or(0, |node| node.2)
+    }
+
+    /// Updates `node`, which covers the stretch between edges `lo` and `hi`.
+    fn update(&mut self, node: usize, lo: usize, hi: usize, span: (i32, i32), delta: i32) {
+        let (top, bottom) = span;
+        if bottom <= self.edges[lo] || self.edges[hi] <= top {
+            return;
+        }
+        if top <= self.edges[lo] && self.edges[hi] <= bottom {
+            self.nodes[node].0 = (self.nodes[node].0 as i32 + delta) as u32;
+        } else {
+            let mid = (lo + hi) / 2;
+            self.update(2 * node, lo, mid, span, delta);
+            self.update(2 * node + 1, mid, hi, span, delta);
+        }
+
+        let len = (i64::from(self.edges[hi]) - i64::from(self.edges[lo])) as u64;
+        let (below_once, below_twice) = if hi - lo > 1 {
+            let (left, right) = (self.nodes[2 * node], self.nodes[2 * node + 1]);
+            (left.1 + right.1, left.2 + right.2)
+        } else {
+            (0, 0)
+        };
+        let covers = self.nodes[node].0;
+        self.nodes[node].1 = if covers >= 1 { len } else { below_once };
+        self.nodes[node].2 = match covers {
+            0 => below_twice,
+            1 => below_once,
+            _ => len,
+        };
+    }
+}
+
+/// The number of squares claimed more than once.
+///
+/// Sweeps a line from left to right across the claims' left and right
+/// edges, keeping how much of it is covered twice, so it needs memory for
+/// the claims' edges rather than for the whole fabric.
+fn double_claimed_squares(claims: &[Claim]) -> u64 {
+    let rects: Vec<&Rect> = claims
         .iter()
-        .filter_map(|(square, count)| if *count > 1 { Some(square) } else { None })
-        .collect()
+        .map(|c| &c.rectangle)
+        .filter(|r| !r.is_empty())
+        .collect();
+    let mut coverage = Coverage::new(rects.iter().flat_map(|r| vec![r.min.y, r.max.y]).collect());
+    let mut sides: Vec<(i32, i32, &Rect)> = rects
+        .iter()
+        .flat_map(|&r| vec![(r.min.x, 1, r), (r.max.x, -1, r)])
+        .collect();
+    sides.sort_unstable_by_key(|side| side.0);
+
+    let mut squares = 0;
+    let mut last_x = sides.first().map_or(0, |side| side.0);
+    for (x, delta, rect) in sides {
+        squares += coverage.twice() * (i64::from(x) - i64::from(last_x)) as u64;
+        coverage.add(rect.min.y, rect.max.y, delta);
+        last_x = x;
+    }
+    squares
 }
 
-fn part_2(claims: &[Claim]) -> Option<&Claim> {
-    for c1 in claims {
-        let mut overlapped = false;
-        for c2 in claims {
-            if c1 != c2 && c1.rectangle.overlaps(&c2.rectangle) {
-                overlapped = true;
-                break;
+/// The fabric from its top left corner, colored by how many claims cover each
+/// square.
+fn render_claims(claims: &[Claim]) -> Result<Image> {
+    let fabric = Fabric::new(claims)?;
+    let drawn = Rect::new(Point::origin(), fabric.bounds().max);
+    drawn
+        .checked_cells(MAX_CELLS)
+        .ok_or_else(|| too_big(&drawn))?;
+    let max = fabric.max_claims();
+    Ok(Image::from_fn(
+        fabric.bounds().max.x as usize,
+        fabric.bounds().max.y as usize,
+        |x, y| render::count_color(fabric.count(&Point::new(x as i32, y as i32)), max),
+    ))
+}
+
+/// An interval tree of rectangles, for finding the ones that overlap a
+/// rectangle without checking every one of them.
+#[derive(Debug)]
+pub struct RectIndex {
+    rects: Vec<Rect>,
+    root: Option<Box<Node>>,
+}
+
+/// A node of a `RectIndex`, holding the rectangles that span its `center`
+/// column.
+#[derive(Debug)]
+struct Node {
+    center: i32,
+    /// The rectangles spanning `center`, by index, leftmost left edge first.
+    by_start: Vec<usize>,
+    /// The same rectangles, rightmost right edge first.
+    by_end: Vec<usize>,
+    /// The rectangles entirely left of `center`.
+    left: Option<Box<Node>>,
+    /// The rectangles entirely right of `center`.
+    right: Option<Box<Node>>,
+}
+
+impl Node {
+    fn build(rects: &[Rect], mut indexes: Vec<usize>) -> Option<Box<Node>> {
+        if indexes.is_empty() {
+            return None;
+        }
+        indexes.sort_by_key(|&i| rects[i].min.x);
+        // The median left edge is in its own rectangle, so each node takes at
+        // least one.
+        let center = rects[indexes[indexes.len() / 2]].min.x;
+        let (mut by_start, mut left, mut right) = (vec![], vec![], vec![]);
+        for i in indexes {
+            if rects[i].max.x <= center {
+                left.push(i);
+            } else if rects[i].min.x > center {
+                right.push(i);
+            } else {
+                by_start.push(i);
             }
         }
-        if !overlapped {
-            return Some(c1);
+        let mut by_end = by_start.clone();
+        by_end.sort_by_key(|&i| Reverse(rects[i].max.x));
+        Some(Box::new(Node {
+            center,
+            by_start,
+            by_end,
+            left: Node::build(rects, left),
+            right: Node::build(rects, right),
+        }))
+    }
+
+    /// Adds the rectangles under this node whose columns overlap `rect`'s to
+    /// `found`.
+    fn columns_overlapping(&self, rects: &[Rect], rect: &Rect, found: &mut Vec<usize>) {
+        let search = |node: &Option<Box<Node>>, found: &mut Vec<usize>| {
+            if let Some(node) = node.as_deref() {
+                node.columns_overlapping(rects, rect, found);
+            }
+        };
+        if rect.max.x <= self.center {
+            let starts_before = |&&i: &&usize| rects[i].min.x < rect.max.x;
+            found.extend(self.by_start.iter().take_while(starts_before));
+            search(&self.left, found);
+        } else if rect.min.x > self.center {
+            let ends_after = |&&i: &&usize| rects[i].max.x > rect.min.x;
+            found.extend(self.by_end.iter().take_while(ends_after));
+            search(&self.right, found);
+        } else {
+            found.extend(&self.by_start);
+            search(&self.left, found);
+            search(&self.right, found);
         }
     }
-    None
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Square {
-    x: u32,
-    y: u32,
+impl RectIndex {
+    pub fn new(rects: &[Rect]) -> RectIndex {
+        let indexes = (0..rects.len()).filter(|&i| !rects[i].is_empty());
+        RectIndex {
+            rects: rects.to_vec(),
+            root: Node::build(rects, indexes.collect()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// The indexes of the rectangles that share at least one square with
+    /// `rect`, in order.
+    pub fn overlapping(&self, rect: &Rect) -> Vec<usize> {
+        let mut found = vec![];
+        if let (Some(root), false) = (self.root.as_deref(), rect.is_empty()) {
+            root.columns_overlapping(&self.rects, rect, &mut found);
+        }
+        found.retain(|&i| self.rects[i].intersects(rect));
+        found.sort_unstable();
+        found
+    }
+}
+
+/// The claims that don't overlap any other claim, in order.
+pub fn intact_claims(claims: &[Claim]) -> Vec<&Claim> {
+    let rects: Vec<Rect> = claims.iter().map(|c| c.rectangle).collect();
+    let index = RectIndex::new(&rects);
+    // Compare positions rather than claims, so that a claim made twice
+    // overlaps itself.
+    claims
+        .iter()
+        .enumerate()
+        .filter(|&(i, claim)| index.overlapping(&claim.rectangle).iter().all(|&j| j == i))
+        .map(|(_, claim)| claim)
+        .collect()
+}
+
+/// Which claims overlap which, and by how much.
+///
+/// Claims are identified by their index in the slice the graph was built
+/// from, so two claims with the same id are still two claims.
+#[derive(Debug, PartialEq)]
+pub struct OverlapGraph {
+    /// Each claim's id.
+    pub ids: Vec<u32>,
+    /// For every claim, the claims it overlaps, each with the number of
+    /// squares they share.
+    pub overlaps: Vec<BTreeMap<usize, u64>>,
 }
 
-named!(parse_u32 <&str, u32>,
-    map_res!(nom::digit, std::str::FromStr::from_str)
-);
+impl OverlapGraph {
+    pub fn new(claims: &[Claim]) -> OverlapGraph {
+        let rects: Vec<Rect> = claims.iter().map(|c| c.rectangle).collect();
+        let index = RectIndex::new(&rects);
+        let mut overlaps = vec![BTreeMap::new(); claims.len()];
+        for (i, others) in overlaps.iter_mut().enumerate() {
+            for j in index.overlapping(&rects[i]) {
+                if let (true, Some(shared)) = (i != j, rects[i].intersection(&rects[j])) {
+                    others.insert(j, squares_in(&shared));
+                }
+            }
+        }
+        OverlapGraph {
+            ids: claims.iter().map(|c| c.id).collect(),
+            overlaps,
+        }
+    }
+
+    /// The squares claim `i` shares with each claim it overlaps, added up
+    /// pair by pair. A square under three claims counts twice.
+    pub fn pairwise_overlap(&self, i: usize) -> u64 {
+        self.overlaps[i].values().sum()
+    }
+
+    /// The claims that overlap another, most conflicted first: by how many
+    /// claims they overlap, then by `pairwise_overlap`, then by id.
+    pub fn most_conflicted(&self) -> Vec<usize> {
+        let mut claims: Vec<usize> = (0..self.ids.len())
+            .filter(|&i| !self.overlaps[i].is_empty())
+            .collect();
+        claims.sort_by_key(|&i| {
+            (
+                Reverse(self.overlaps[i].len()),
+                Reverse(self.pairwise_overlap(i)),
+                self.ids[i],
+                i,
+            )
+        });
+        claims
+    }
 
-named!(parse_claim(&str) -> Claim,
-    do_parse!(
-        tag!("#") >>
-        id: terminated!(parse_u32, tag!(" @ ")) >>
-        left: terminated!(parse_u32, tag!(",")) >>
-        top: terminated!(parse_u32, tag!(": ")) >>
-        width: terminated!(parse_u32, tag!("x")) >>
-        height: parse_u32 >>
+    /// The groups of claims joined by overlaps, leaving out the claims that
+    /// don't overlap anything. Each group is in order, and the groups are in
+    /// order of their first claims.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.ids.len()];
+        let mut components = vec![];
+        for (i, others) in self.overlaps.iter().enumerate() {
+            if others.is_empty() || seen[i] {
+                continue;
+            }
+            seen[i] = true;
+            let mut component = vec![i];
+            let mut next = 0;
+            while next < component.len() {
+                for &other in self.overlaps[component[next]].keys() {
+                    if !seen[other] {
+                        seen[other] = true;
+                        component.push(other);
+                    }
+                }
+                next += 1;
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
 
-        ( Claim {
-            id,
-            rectangle: Rectangle {
-                left, top, width, height
+    /// A line for each claim that overlaps another, most conflicted first,
+    /// then a summary.
+    pub fn report(&self) -> String {
+        let count = |n: usize, what: &str| {
+            if n == 1 {
+                format!("1 {}", what)
+            } else {
+                format!("{} {}s", n, what)
             }
-        } )
-       ));
+        };
+        let mut report = String::new();
+        for i in self.most_conflicted() {
+            let others: Vec<String> = self.overlaps[i]
+                .iter()
+                .map(|(&other, area)| format!("#{} ({})", self.ids[other], area))
+                .collect();
+            writeln!(
+                report,
+                "#{} overlaps {}, {} pairwise: {}",
+                self.ids[i],
+                count(others.len(), "claim"),
+                count(self.pairwise_overlap(i) as usize, "square"),
+                others.join(", ")
+            )
+            .unwrap();
+        }
+
+        let components = self.components();
+        let conflicted: usize = components.iter().map(|c| c.len()).sum();
+        match components.iter().map(|c| c.len()).max() {
+            Some(largest) => writeln!(
+                report,
+                "{} of {} overlap another, in {} of up to {}",
+                conflicted,
+                count(self.ids.len(), "claim"),
+                count(components.len(), "group"),
+                count(largest, "claim")
+            ),
+            None => writeln!(report, "No claims overlap"),
+        }
+        .unwrap();
+        report
+    }
+}
+
+fn part_2(claims: &[Claim]) -> Option<&Claim> {
+    intact_claims(claims).into_iter().next()
+}
 
-named!(parse_claims(&str) -> Vec<Claim>,
-       terminated!(separated_list_complete!(char!('\n'), parse_claim), tag!("\n"))
-);
+// #123 @ 3,2: 5x4
+//
+// Claims of no width or height are fine, but not ones whose far edges are
+// past the largest coordinate.
+fn claim<'a>(
+) -> impl Parser<Input = parse::Stream<'a>, Output = ::std::result::Result<Claim, String>> {
+    let id = tag("#").with(unsigned());
+    let corner = (unsigned(), tag(","), unsigned()).map(|t| Point::new(t.0, t.2));
+    let size = (unsigned(), tag("x"), unsigned()).map(|t| (t.0, t.2));
+
+    (id, field(tag("@")), corner, field(tag(":")), size).map(|t| {
+        let (id, corner, (width, height)) = (t.0, t.2, t.4);
+        Rect::checked_with_size(corner, width, height)
+            .map(|rectangle| Claim { id, rectangle })
+            .ok_or_else(|| {
+                format!(
+                    "claim #{} runs off the fabric: {}x{} from {},{} is past {}",
+                    id,
+                    width,
+                    height,
+                    corner.x,
+                    corner.y,
+                    i32::MAX
+                )
+            })
+    })
+}
 
 #[cfg(test)]
 mod test {
 
     use super::*;
+    use geom::Vector;
 
     fn example_claims() -> Vec<Claim> {
         vec![
             Claim {
                 id: 1,
-                rectangle: Rectangle {
-                    left: 1,
-                    top: 3,
-                    width: 4,
-                    height: 4,
-                },
+                rectangle: Rect::with_size(Point::new(1, 3), 4, 4),
             },
             Claim {
                 id: 2,
-                rectangle: Rectangle {
-                    left: 3,
-                    top: 1,
-                    width: 4,
-                    height: 4,
-                },
+                rectangle: Rect::with_size(Point::new(3, 1), 4, 4),
             },
             Claim {
                 id: 3,
-                rectangle: Rectangle {
-                    left: 5,
-                    top: 5,
-                    width: 2,
-                    height: 2,
-                },
+                rectangle: Rect::with_size(Point::new(5, 5), 2, 2),
             },
         ]
     }
@@ -157,66 +673,208 @@ mod test {
     #[test]
     fn test_claimed_squares() {
         assert_eq!(
-            (Rectangle {
-                left: 2,
-                top: 3,
-                width: 1,
-                height: 1,
-            }).claimed_squares(),
-            vec![Square { x: 2, y: 3 }].into_iter().collect()
+            Rect::with_size(Point::new(2, 3), 1, 1)
+                .points()
+                .collect::<Vec<_>>(),
+            vec![Point::new(2, 3)]
         );
     }
     #[test]
     fn test_claimed_square_counts() {
-        assert_eq!(
-            double_claimed_squares(&claimed_square_counts(&example_claims()[..])),
-            [
-                Square { x: 4, y: 4 },
-                Square { x: 3, y: 4 },
-                Square { x: 3, y: 3 },
-                Square { x: 4, y: 3 }
-            ]
+        let claims = example_claims();
+        let counts = ClaimCounts::new(&claims).unwrap();
+        assert_eq!(counts.bounds, Rect::new(Point::new(1, 1), Point::new(7, 7)));
+        for square in counts.bounds.expanded(1).points() {
+            let covering = claims
                 .iter()
-                .collect::<HashSet<&Square>>()
-        )
+                .filter(|c| c.rectangle.contains(&square))
+                .count();
+            assert_eq!(counts.get(&square) as usize, covering, "{:?}", square);
+        }
+        assert_eq!(counts.max(), 2);
+        assert_eq!(ClaimCounts::new(&[]).unwrap().max(), 0);
+    }
+
+    #[test]
+    fn test_fabric() {
+        let mut claims = example_claims();
+        claims.push(Claim {
+            id: 4,
+            rectangle: Rect::with_size(Point::new(4, 0), 1, 6),
+        });
+        let fabric = Fabric::new(&claims).unwrap();
+        assert_eq!(
+            fabric.bounds(),
+            Rect::new(Point::new(1, 0), Point::new(7, 7))
+        );
+        assert_eq!(fabric.max_claims(), 3);
+
+        assert_eq!(fabric.count(&Point::new(4, 3)), 3);
+        assert_eq!(fabric.claims_at(&Point::new(4, 3)), vec![1, 2, 4]);
+        assert_eq!(fabric.claims_at(&Point::new(5, 5)), vec![3]);
+        assert_eq!(fabric.claims_at(&Point::new(0, 0)), vec![]);
+
+        // Every window, against counting its squares one by one.
+        let around = fabric.bounds().expanded(1);
+        for min in around.points() {
+            for max in Rect::new(min, around.max + Vector::new(1, 1)).points() {
+                let window = Rect::new(min, max);
+                let mut histogram = vec![0; 4];
+                for square in window.points() {
+                    histogram[fabric.count(&square) as usize] += 1;
+                }
+                assert_eq!(fabric.histogram(&window), histogram, "{:?}", window);
+                assert_eq!(
+                    fabric.claimed_at_least(&window, 2),
+                    histogram[2] + histogram[3]
+                );
+            }
+        }
+        assert_eq!(fabric.claimed_at_least(&fabric.bounds(), 4), 0);
+        assert_eq!(fabric.claimed_at_least(&around, 0), 72);
+        assert_eq!(
+            fabric.at_least.borrow().keys().collect::<Vec<_>>(),
+            vec![&1, &2, &3]
+        );
+        assert_eq!(fabric.double_claimed_squares(), 7);
+
+        assert_eq!(double_claimed_squares(&example_claims()), 4);
+        assert_eq!(double_claimed_squares(&claims), 7);
+        let empty = Fabric::new(&[]).unwrap();
+        let window = Rect::with_size(Point::new(0, 0), 2, 3);
+        assert_eq!(empty.histogram(&window), vec![6]);
+        assert_eq!(empty.claimed_at_least(&window, 1), 0);
+        assert_eq!(empty.double_claimed_squares(), 0);
+        assert_eq!(double_claimed_squares(&[]), 0);
     }
 
     #[test]
     fn test_part_2() {
-        let ex = example_claims();
+        let mut ex = example_claims();
         assert_eq!(part_2(&ex), Some(&ex[2]));
+
+        ex.push(Claim {
+            id: 4,
+            rectangle: Rect::with_size(Point::new(8, 0), 1, 1),
+        });
+        assert_eq!(intact_claims(&ex), vec![&ex[2], &ex[3]]);
+
+        // The same claim twice overlaps itself.
+        ex.push(Claim {
+            id: 3,
+            rectangle: Rect::with_size(Point::new(5, 5), 2, 2),
+        });
+        assert_eq!(intact_claims(&ex), vec![&ex[3]]);
+        assert_eq!(part_2(&ex[..2]), None);
+    }
+
+    #[test]
+    fn test_overlap_graph() {
+        let mut ex = example_claims();
+        ex.push(Claim {
+            id: 4,
+            rectangle: Rect::with_size(Point::new(4, 0), 1, 6),
+        });
+        ex.push(Claim {
+            id: 5,
+            rectangle: Rect::with_size(Point::new(9, 9), 1, 1),
+        });
+        let graph = OverlapGraph::new(&ex);
+
+        let overlaps = |i: usize| {
+            graph.overlaps[i]
+                .iter()
+                .map(|(&k, &v)| (k, v))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(graph.ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(overlaps(0), vec![(1, 4), (3, 3)]);
+        assert_eq!(overlaps(1), vec![(0, 4), (3, 4)]);
+        assert_eq!(overlaps(2), vec![]);
+        assert_eq!(overlaps(3), vec![(0, 3), (1, 4)]);
+        assert_eq!(graph.pairwise_overlap(3), 7);
+
+        assert_eq!(graph.most_conflicted(), vec![1, 0, 3]);
+        assert_eq!(graph.components(), vec![vec![0, 1, 3]]);
+        assert_eq!(
+            graph.report(),
+            "#2 overlaps 2 claims, 8 squares pairwise: #1 (4), #4 (4)
+#1 overlaps 2 claims, 7 squares pairwise: #2 (4), #4 (3)
+#4 overlaps 2 claims, 7 squares pairwise: #1 (3), #2 (4)
+3 of 5 claims overlap another, in 1 group of up to 3 claims
+"
+        );
+
+        let intact = OverlapGraph::new(&ex[2..3]);
+        assert!(intact.components().is_empty());
+        assert_eq!(intact.report(), "No claims overlap\n");
+
+        // Claims with the same id are still separate claims.
+        let twins = Day3::parse("#7 @ 0,0: 2x2\n#7 @ 1,1: 2x2\n#8 @ 5,5: 1x1").unwrap();
+        let graph = OverlapGraph::new(&twins);
+        assert_eq!(graph.components(), vec![vec![0, 1]]);
+        assert_eq!(
+            graph.report(),
+            "#7 overlaps 1 claim, 1 square pairwise: #7 (1)
+#7 overlaps 1 claim, 1 square pairwise: #7 (1)
+2 of 3 claims overlap another, in 1 group of up to 2 claims
+"
+        );
+    }
+
+    #[test]
+    fn test_rect_index() {
+        let rects: Vec<Rect> = (0..60)
+            .map(|i| Rect::with_size(Point::new(i * 7 % 31, i * 13 % 29), i % 9, i * 5 % 7))
+            .collect();
+        let index = RectIndex::new(&rects);
+        assert_eq!(index.len(), 60);
+        for query in rects.iter().chain(&[
+            Rect::with_size(Point::new(-5, -5), 100, 100),
+            Rect::with_size(Point::new(10, 10), 1, 1),
+            Rect::with_size(Point::new(10, 10), 0, 5),
+        ]) {
+            let expected: Vec<usize> = (0..rects.len())
+                .filter(|&i| rects[i].intersects(query))
+                .collect();
+            assert_eq!(index.overlapping(query), expected, "{:?}", query);
+        }
+        assert!(RectIndex::new(&[]).overlapping(&rects[1]).is_empty());
     }
 
     #[test]
     fn test_overlaps() {
         let ex = example_claims();
-        assert!(ex[0].rectangle.overlaps(&ex[0].rectangle));
-        assert!(ex[0].rectangle.overlaps(&ex[1].rectangle));
-        assert!(!ex[0].rectangle.overlaps(&ex[2].rectangle));
-        assert!(ex[1].rectangle.overlaps(&ex[0].rectangle));
-        assert!(ex[1].rectangle.overlaps(&ex[1].rectangle));
-        assert!(!ex[1].rectangle.overlaps(&ex[2].rectangle));
-        assert!(!ex[2].rectangle.overlaps(&ex[0].rectangle));
-        assert!(!ex[2].rectangle.overlaps(&ex[1].rectangle));
-        assert!(ex[2].rectangle.overlaps(&ex[2].rectangle));
+        assert!(ex[0].rectangle.intersects(&ex[0].rectangle));
+        assert!(ex[0].rectangle.intersects(&ex[1].rectangle));
+        assert!(!ex[0].rectangle.intersects(&ex[2].rectangle));
+        assert!(ex[1].rectangle.intersects(&ex[0].rectangle));
+        assert!(ex[1].rectangle.intersects(&ex[1].rectangle));
+        assert!(!ex[1].rectangle.intersects(&ex[2].rectangle));
+        assert!(!ex[2].rectangle.intersects(&ex[0].rectangle));
+        assert!(!ex[2].rectangle.intersects(&ex[1].rectangle));
+        assert!(ex[2].rectangle.intersects(&ex[2].rectangle));
+    }
+
+    #[test]
+    fn test_render_claims() {
+        let image = render_claims(&example_claims()).unwrap();
+        assert_eq!((image.width, image.height), (7, 7));
+        assert_eq!(image.get(0, 0), Some(render::Color::BLACK));
+        assert_eq!(image.get(1, 3), Some(render::count_color(1, 2)));
+        assert_eq!(image.get(3, 3), Some(render::count_color(2, 2)));
     }
 
     #[test]
     fn test_claim_parser() {
-        assert_eq!((parse_u32("123 ")), Ok((" ", 123)));
         assert_eq!(
-            parse_claim("#123 @ 3,2: 5x4 "),
+            claim().easy_parse("#123 @ 3,2: 5x4 "),
             Ok((
-                " ",
-                Claim {
+                Ok(Claim {
                     id: 123,
-                    rectangle: Rectangle {
-                        left: 3,
-                        top: 2,
-                        width: 5,
-                        height: 4
-                    }
-                }
+                    rectangle: Rect::with_size(Point::new(3, 2), 5, 4)
+                }),
+                " "
             ))
         );
     }
@@ -224,6 +882,92 @@ mod test {
     #[test]
     fn test_claims_parser() {
         let claims_str = ["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2", ""].join("\n");
-        assert_eq!(parse_claims(&claims_str), Ok(("", example_claims())));
+        assert_eq!(Day3::parse(&claims_str).unwrap(), example_claims());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let claims_str = ["#1 @ 1,3: 4x4", "#2 @ 3;1: 4x4", ""].join("\n");
+        assert_eq!(
+            Day3::parse(&claims_str).unwrap_err().to_string(),
+            "parse error at line 2, column 7: Unexpected `;`; Expected `,`"
+        );
+
+        let claims_str = "#1 @ 1,3: 4x4\n#2 @ 2147483000,1: 1000x4\n";
+        assert_eq!(
+            Day3::parse(claims_str).unwrap_err().to_string(),
+            "parse error at line 2, column 1: claim #2 runs off the fabric: 1000x4 from 2147483000,1 is past 2147483647"
+        );
+        assert!(Day3::parse("#1 @ 1,3: 2147483648x4").is_err());
+    }
+
+    #[test]
+    fn test_zero_size_claims() {
+        let claims =
+            Day3::parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 0x4\n#3 @ 3,3: 5x0\n#4 @ 0,0: 0x0").unwrap();
+        assert!(claims[1].rectangle.is_empty());
+        assert_eq!(Day3::part_1(&claims).unwrap(), 0);
+        assert_eq!(
+            intact_claims(&claims)
+                .iter()
+                .map(|c| c.id)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+
+        let fabric = Fabric::new(&claims).unwrap();
+        assert_eq!(fabric.bounds(), claims[0].rectangle);
+        assert_eq!(fabric.claims_at(&Point::new(3, 3)), vec![1]);
+        assert_eq!(fabric.histogram(&fabric.bounds()), vec![0, 16]);
+        assert!(OverlapGraph::new(&claims).components().is_empty());
+        assert_eq!(render_claims(&claims[1..]).unwrap().width, 0);
+    }
+
+    #[test]
+    fn test_double_claimed_squares() {
+        let claims: Vec<Claim> = (0..80)
+            .map(|i| Claim {
+                id: i as u32,
+                rectangle: Rect::with_size(Point::new(i * 7 % 31, i * 13 % 29), i % 9, i * 5 % 7),
+            })
+            .collect();
+        let counts = ClaimCounts::new(&claims).unwrap();
+        assert_eq!(
+            double_claimed_squares(&claims),
+            counts.counts.iter().filter(|&&count| count >= 2).count() as u64
+        );
+
+        // Far out and far bigger than the fabric could hold.
+        let claims = Day3::parse(
+            "#1 @ 1000000,1000000: 100000x100000\n#2 @ 1050000,1050000: 100000x100000\n\
+             #3 @ 1050000,1050000: 10x10",
+        )
+        .unwrap();
+        assert_eq!(double_claimed_squares(&claims), 50_000 * 50_000);
+    }
+
+    #[test]
+    fn test_fabric_too_big() {
+        // Small claims, but far apart.
+        let claims = Day3::parse("#1 @ 0,0: 1x1\n#2 @ 100000,100000: 1x1").unwrap();
+        assert_eq!(
+            Fabric::new(&claims).unwrap_err().to_string(),
+            "no solution: the fabric is too big: 100001x100001 squares is too many"
+        );
+        assert!(Day3::render(&claims).is_err());
+        // Part 1 doesn't need the whole fabric.
+        assert_eq!(Day3::part_1(&claims).unwrap(), 0);
+        assert_eq!(Day3::part_2(&claims).unwrap(), 1);
+
+        // A lone claim far out fits, but there's too much fabric to draw up
+        // to it.
+        let claims = Day3::parse("#2 @ 100000,100000: 1x1").unwrap();
+        assert_eq!(Day3::part_1(&claims).unwrap(), 0);
+        assert!(Day3::render(&claims).is_err());
+
+        let claims = Day3::parse(&format!("#1 @ {},0: 1x1", i32::MAX - 1)).unwrap();
+        let fabric = Fabric::new(&claims).unwrap();
+        assert_eq!(fabric.claims_at(&Point::new(i32::MAX - 1, 0)), vec![1]);
+        assert_eq!(fabric.claims_at(&Point::new(i32::MAX, 0)), vec![]);
     }
 }
@@ -1,27 +1,43 @@
-use combine::parser::char::{char, digit, newline, string};
-use combine::Parser;
-use combine::Stream;
-use combine::{between, choice, count_min_max, from_str, many1, sep_by};
+// Written before these lints existed.
+#![allow(
+    clippy::single_match,
+    clippy::needless_borrow,
+    clippy::redundant_closure,
+    clippy::useless_vec
+)]
+
+use combine::parser::char::{char, string};
+use combine::{between, choice};
 
 use std::collections::HashMap;
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read data file.");
+use error::{Error, Result};
+use parse::{self, digits, unsigned};
+use solution::Solution;
+
+pub struct Day4;
 
-    let (mut events, _): (Vec<Event>, _) = sep_by(event(), newline())
-        .easy_parse(&input[..])
-        .expect("Couldn't parse input events");
+impl Solution for Day4 {
+    type Input = Vec<Event>;
+    type Part1 = u32;
+    type Part2 = u32;
 
-    events.sort_by(|e1, e2| e1.ts.cmp(&e2.ts));
+    fn parse(input: &str) -> Result<Vec<Event>> {
+        let mut events = parse::lines_of(input, event())?;
 
-    let part_1_solution = part_1(&events);
-    println!("day 4, part 1: {:?}", part_1_solution);
+        events.sort_by(|e1, e2| e1.ts.cmp(&e2.ts));
+        Ok(events)
+    }
 
-    let part_2_solution = part_2(&events);
-    println!(
-        "day 4, part 2: {:?}",
-        part_2_solution.map(|(guard_id, minute)| guard_id * (minute as u16))
-    );
+    fn part_1(events: &Vec<Event>) -> Result<u32> {
+        part_1(events).ok_or_else(|| Error::no_solution("no guard fell asleep"))
+    }
+
+    fn part_2(events: &Vec<Event>) -> Result<u32> {
+        part_2(events)
+            .map(|(guard_id, minute)| u32::from(guard_id) * u32::from(minute))
+            .ok_or_else(|| Error::no_solution("no guard fell asleep"))
+    }
 }
 
 type Minute = u8;
@@ -44,7 +60,7 @@ enum EventKind {
 }
 
 #[derive(Debug, PartialEq)]
-struct Event {
+pub struct Event {
     ts: Timestamp,
     kind: EventKind,
 }
@@ -110,13 +126,13 @@ fn most_sleepy_guard(
     max_entry(&total_minutes_asleep(minute_counts_by_guard)).map(|t| t.0)
 }
 
-fn part_1(events: &[Event]) -> Option<u16> {
-    let m = minutes_asleep(&events);
+fn part_1(events: &[Event]) -> Option<u32> {
+    let m = minutes_asleep(events);
     let sleepiest_guard = most_sleepy_guard(&m);
     sleepiest_guard.and_then(|id| {
         m.get(&id)
             .and_then(|minute_counts| max_entry(&minute_counts))
-            .map(|(minute, _)| id * (minute as u16))
+            .map(|(minute, _)| u32::from(id) * u32::from(minute))
     })
 }
 
@@ -134,23 +150,19 @@ fn part_2(events: &[Event]) -> Option<(GuardID, Minute)> {
 // [1518-11-01 00:00] Guard #10 begins shift
 
 parser!{
-    fn timestamp[I]()(I) -> Timestamp
-    where [I: Stream<Item = char>]
+    fn timestamp['a]()(parse::Stream<'a>) -> Timestamp
     {
-        let digits_u16 = |n| from_str(count_min_max::<String, _>(n, n, digit()));
-        let digits_u8 = |n| from_str(count_min_max::<String, _>(n, n, digit()));
-
         struct_parser!{
             Timestamp {
-                year: digits_u16(4),
+                year: digits(4),
                 _: char('-'),
-                month: digits_u8(2),
+                month: digits(2),
                 _: char('-'),
-                day: digits_u8(2),
+                day: digits(2),
                 _: char(' '),
-                hour: digits_u8(2),
+                hour: digits(2),
                 _: char(':'),
-                minute: digits_u8(2),
+                minute: digits(2),
             }
         }
 
@@ -158,11 +170,10 @@ parser!{
 }
 
 parser!{
-    fn event_kind[I]()(I) -> EventKind
-    where [I: Stream<Item = char>]
+    fn event_kind['a]()(parse::Stream<'a>) -> EventKind
     {
         let begin_shift = || between(string("Guard #"), string(" begins shift"),
-                                     from_str(many1::<String,_>(digit()))).map(|id| EventKind::BeginsShift(id));
+                                     unsigned()).map(|id| EventKind::BeginsShift(id));
         let falls_asleep = || string("falls asleep").map(|_| EventKind::FallsAsleep);
         let wakes_up = || string("wakes up").map(|_| EventKind::WakesUp);
 
@@ -171,8 +182,7 @@ parser!{
 }
 
 parser!{
-    fn event[I]()(I) -> Event
-    where [I: Stream<Item = char>]
+    fn event['a]()(parse::Stream<'a>) -> Event
     {
         struct_parser!{
             Event {
@@ -256,9 +266,7 @@ mod test {
             "[1518-11-03 00:08] wakes up",
         ].join("\n");
 
-        let (events, _): (Vec<Event>, _) = sep_by(event(), newline())
-            .easy_parse(&records[..])
-            .expect("Couldn't parse input events");
+        let events = parse::lines_of(&records, event()).expect("Couldn't parse input events");
 
         let mut expected = HashMap::new();
         let mut guard_10 = HashMap::new();
@@ -290,9 +298,7 @@ mod test {
             "[1518-11-05 00:55] wakes up",
         ].join("\n");
 
-        let (events, _): (Vec<Event>, _) = sep_by(event(), newline())
-            .easy_parse(&records[..])
-            .expect("Couldn't parse input events");
+        let events = parse::lines_of(&records, event()).expect("Couldn't parse input events");
         events
     }
 
@@ -322,4 +328,21 @@ mod test {
 
         assert_eq!(part_2(&events), Some((99, 45)))
     }
+
+    #[test]
+    fn test_large_guard_ids() {
+        let records = [
+            "[1518-11-01 00:00] Guard #3000 begins shift",
+            "[1518-11-01 00:05] falls asleep",
+            "[1518-11-01 00:25] wakes up",
+            "[1518-11-02 00:00] Guard #3000 begins shift",
+            "[1518-11-02 00:23] falls asleep",
+            "[1518-11-02 00:24] wakes up",
+        ]
+        .join("\n");
+        let events = Day4::parse(&records).unwrap();
+
+        assert_eq!(Day4::part_1(&events).unwrap(), 69000);
+        assert_eq!(Day4::part_2(&events).unwrap(), 69000);
+    }
 }
@@ -1,13 +1,36 @@
+// Written before these lints existed.
+#![allow(clippy::manual_ignore_case_cmp, clippy::collapsible_str_replace)]
+
 use std::collections::HashMap;
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read data file.");
+use combine::Parser;
+
+use error::{Error, Result};
+use parse;
+use solution::Solution;
+
+pub struct Day5;
 
-    let part_1_solution = react(&input);
-    println!("day 5, part 1: {:?}", part_1_solution.len());
+impl Solution for Day5 {
+    type Input = String;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<String> {
+        // Every unit is a letter, and there may be none of them.
+        let units = combine::parser::range::take_while(|c: char| c.is_ascii_alphabetic());
+        parse::parse_all(input, units.map(String::from))
+    }
 
-    let part_2_solution = part_2(&input);
-    println!("day 5, part 2: {:?}", part_2_solution);
+    fn part_1(polymer: &String) -> Result<usize> {
+        Ok(react(polymer).len())
+    }
+
+    fn part_2(polymer: &String) -> Result<usize> {
+        part_2(polymer)
+            .map(|(_unit, len)| len)
+            .ok_or_else(|| Error::no_solution("polymer has no units"))
+    }
 }
 
 fn units_react(c1: char, c2: char) -> bool {
@@ -60,7 +83,6 @@ fn part_2(polymer: &str) -> Option<(char, usize)> {
 }
 
 #[cfg(test)]
-
 mod test {
     use super::*;
 
@@ -1,150 +1,888 @@
 use combine::Parser;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::str::FromStr;
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read data file");
+use error::{Error, Result};
+use geom::{Point, Rect, Vector, MAX_CELLS};
+use parse::{self, field, signed, tag};
+use render::{self, Color, Image};
+use solution::{Setting, Solution};
 
-    let (coords, _): (Vec<_>, _) = combine::sep_by(point(), combine::parser::char::newline())
-        .easy_parse(&input[..])
-        .expect("Couldn't parse input coords");
+pub struct Day6;
 
-    let part_1_solution = part_1(&coords);
-    println!("Day 6, part 1: {}", part_1_solution.unwrap());
+impl Solution for Day6 {
+    type Input = Coords;
+    type Part1 = usize;
+    type Part2 = usize;
 
-    let part_2_solution = part_2(&coords);
-    println!("Day 6, part 2: {}", part_2_solution.unwrap());
+    fn parse(input: &str) -> Result<Coords> {
+        Ok(Coords {
+            points: parse::lines_of(input, point())?,
+            metric: AnyMetric::Manhattan(Manhattan),
+            search: Search::Bounds,
+        })
+    }
+
+    fn part_1(coords: &Coords) -> Result<usize> {
+        part_1(&coords.points, &coords.metric)?.ok_or_else(|| Error::no_solution("no finite areas"))
+    }
+
+    fn part_2(coords: &Coords) -> Result<usize> {
+        part_2(&coords.points, &coords.metric, coords.search)?
+            .ok_or_else(|| Error::no_solution("no coordinates given"))
+    }
+
+    fn render(coords: &Coords) -> Result<Option<Image>> {
+        match bounds(&coords.points)? {
+            Some(bounds) => render_areas(&coords.points, &bounds, &coords.metric, 10000).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// `metric=<metric>` measures distances with one of `AnyMetric`, and
+    /// `search=bounds` or `search=everywhere` says where part 2 looks.
+    fn set_option(coords: &mut Coords, setting: &Setting) -> Result<()> {
+        match setting.name.as_ref() {
+            "metric" => {
+                coords.metric = setting.value.parse().map_err(Error::bad_option)?;
+                Ok(())
+            }
+            "search" => {
+                coords.search = setting.value.parse().map_err(Error::bad_option)?;
+                Ok(())
+            }
+            name => Err(Error::bad_option(format!("unknown option {:?}", name))),
+        }
+    }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
-struct Point {
-    x: u32,
-    y: u32,
+/// The puzzle's coordinates, and how to measure distances between them.
+#[derive(Debug)]
+pub struct Coords {
+    pub points: Vec<Point>,
+    pub metric: AnyMetric,
+    pub search: Search,
 }
 
-fn manhattan_dist(p1: &Point, p2: &Point) -> u32 {
-    let abs_diff = |a, b| if a > b { a - b } else { b - a };
-    abs_diff(p1.x, p2.x) + abs_diff(p1.y, p2.y)
+/// Where part 2 looks for the safe region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Search {
+    /// Only within the coordinates' bounds.
+    Bounds,
+    /// Wherever the region reaches, which can be past the coordinates if the
+    /// safe distance is large compared to how far apart they are.
+    Everywhere,
 }
 
-fn closest_coords<'a>(coords: &'a [Point], p: &Point) -> Vec<&'a Point> {
-    if coords.len() == 0 {
-        return vec![];
+impl FromStr for Search {
+    type Err = String;
+
+    fn from_str(s: &str) -> ::std::result::Result<Search, String> {
+        match s {
+            "bounds" => Ok(Search::Bounds),
+            "everywhere" => Ok(Search::Everywhere),
+            _ => Err(format!(
+                "unknown search {:?}: expected bounds or everywhere",
+                s
+            )),
+        }
     }
+}
 
-    let mut ranked: Vec<(&Point, u32)> =
-        coords.iter().map(|c| (c, manhattan_dist(&p, c))).collect();
-    ranked.sort_by(|(_c1, dist1), (_c2, dist2)| dist1.cmp(dist2));
-    let smallest_dist = ranked[0].1;
-    ranked
-        .iter()
-        .take_while(|(_c, dist)| *dist == smallest_dist)
-        .map(|(c, _dist)| *c)
-        .collect()
+/// A way of measuring the distance between two squares.
+pub trait Metric {
+    fn dist(&self, a: &Point, b: &Point) -> i64;
+
+    /// Whether moving straight out from a square beyond all the coordinates
+    /// adds the same distance to every one of them. If so, an area that
+    /// reaches the edge of the coordinates' bounds goes on forever, and a
+    /// finite one never gets that far.
+    fn uniform_outside(&self) -> bool {
+        false
+    }
+
+    /// Moves between neighbouring squares and what each one costs, if the
+    /// distance between any two squares is the cost of the cheapest way
+    /// between them by those moves.
+    fn moves(&self) -> Option<Vec<(Vector, i64)>> {
+        None
+    }
+
+    /// What a step along `x` and a step along `y` cost, if the distance is
+    /// the sum of the two. Then the total distance from a square to the
+    /// coordinates is a total along `x` plus a total along `y`.
+    fn axis_weights(&self) -> Option<(i64, i64)> {
+        None
+    }
+}
+
+/// The moves to the four squares that share an edge with a square.
+fn orthogonal_moves(x_cost: i64, y_cost: i64) -> Vec<(Vector, i64)> {
+    vec![
+        (Vector::new(1, 0), x_cost),
+        (Vector::new(-1, 0), x_cost),
+        (Vector::new(0, 1), y_cost),
+        (Vector::new(0, -1), y_cost),
+    ]
+}
+
+fn widen(p: &Point) -> Point<i64> {
+    Point::new(i64::from(p.x), i64::from(p.y))
+}
+
+/// Distance along the grid lines, as in the puzzle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    fn dist(&self, a: &Point, b: &Point) -> i64 {
+        widen(a).manhattan_dist(&widen(b))
+    }
+
+    fn uniform_outside(&self) -> bool {
+        true
+    }
+
+    fn moves(&self) -> Option<Vec<(Vector, i64)>> {
+        Some(orthogonal_moves(1, 1))
+    }
+
+    fn axis_weights(&self) -> Option<(i64, i64)> {
+        Some((1, 1))
+    }
+}
+
+/// The number of king moves between squares.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chebyshev;
+
+impl Metric for Chebyshev {
+    fn dist(&self, a: &Point, b: &Point) -> i64 {
+        widen(a).chebyshev_dist(&widen(b))
+    }
+
+    fn moves(&self) -> Option<Vec<(Vector, i64)>> {
+        let mut moves = orthogonal_moves(1, 1);
+        for &(x, y) in [(1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+            moves.push((Vector::new(x, y), 1));
+        }
+        Some(moves)
+    }
+}
+
+/// The square of the straight-line distance, which puts squares in the same
+/// order as the distance itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EuclideanSquared;
+
+impl Metric for EuclideanSquared {
+    fn dist(&self, a: &Point, b: &Point) -> i64 {
+        widen(a).euclidean_dist_squared(&widen(b))
+    }
+}
+
+/// Manhattan distance where each step along `x` costs `x` and each step
+/// along `y` costs `y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weighted {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Metric for Weighted {
+    fn dist(&self, a: &Point, b: &Point) -> i64 {
+        let d = widen(b) - widen(a);
+        self.x * d.x.abs() + self.y * d.y.abs()
+    }
+
+    fn uniform_outside(&self) -> bool {
+        true
+    }
+
+    fn moves(&self) -> Option<Vec<(Vector, i64)>> {
+        Some(orthogonal_moves(self.x, self.y))
+    }
+
+    fn axis_weights(&self) -> Option<(i64, i64)> {
+        Some((self.x, self.y))
+    }
 }
 
-fn calc_areas(coords: &[Point], max_point: &Point) -> HashMap<Point, HashSet<Point>> {
-    let mut areas = HashMap::new();
+/// Any of the metrics, for choosing one at run time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnyMetric {
+    Manhattan(Manhattan),
+    Chebyshev(Chebyshev),
+    EuclideanSquared(EuclideanSquared),
+    Weighted(Weighted),
+}
 
-    for x in 0..max_point.x + 1 {
-        for y in 0..max_point.y + 1 {
-            let p = Point { x, y };
-            let closest_coords = closest_coords(&coords, &p);
-            if closest_coords.len() != 1 {
+impl Metric for AnyMetric {
+    fn dist(&self, a: &Point, b: &Point) -> i64 {
+        match *self {
+            AnyMetric::Manhattan(ref m) => m.dist(a, b),
+            AnyMetric::Chebyshev(ref m) => m.dist(a, b),
+            AnyMetric::EuclideanSquared(ref m) => m.dist(a, b),
+            AnyMetric::Weighted(ref m) => m.dist(a, b),
+        }
+    }
+
+    fn uniform_outside(&self) -> bool {
+        match *self {
+            AnyMetric::Manhattan(ref m) => m.uniform_outside(),
+            AnyMetric::Chebyshev(ref m) => m.uniform_outside(),
+            AnyMetric::EuclideanSquared(ref m) => m.uniform_outside(),
+            AnyMetric::Weighted(ref m) => m.uniform_outside(),
+        }
+    }
+
+    fn moves(&self) -> Option<Vec<(Vector, i64)>> {
+        match *self {
+            AnyMetric::Manhattan(ref m) => m.moves(),
+            AnyMetric::Chebyshev(ref m) => m.moves(),
+            AnyMetric::EuclideanSquared(ref m) => m.moves(),
+            AnyMetric::Weighted(ref m) => m.moves(),
+        }
+    }
+
+    fn axis_weights(&self) -> Option<(i64, i64)> {
+        match *self {
+            AnyMetric::Manhattan(ref m) => m.axis_weights(),
+            AnyMetric::Chebyshev(ref m) => m.axis_weights(),
+            AnyMetric::EuclideanSquared(ref m) => m.axis_weights(),
+            AnyMetric::Weighted(ref m) => m.axis_weights(),
+        }
+    }
+}
+
+/// `manhattan`, `chebyshev`, `euclidean` or `weighted:<x>,<y>`, where the
+/// weights are positive.
+impl FromStr for AnyMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> ::std::result::Result<AnyMetric, String> {
+        let weights = |w: &str| -> Option<Weighted> {
+            let mut w = w.splitn(2, ',').map(|n| n.trim().parse::<i64>().ok());
+            match (w.next()??, w.next()??) {
+                (x, y) if x > 0 && y > 0 => Some(Weighted { x, y }),
+                _ => None,
+            }
+        };
+        match s {
+            "manhattan" => Ok(AnyMetric::Manhattan(Manhattan)),
+            "chebyshev" => Ok(AnyMetric::Chebyshev(Chebyshev)),
+            "euclidean" => Ok(AnyMetric::EuclideanSquared(EuclideanSquared)),
+            _ if s.starts_with("weighted:") => weights(&s["weighted:".len()..])
+                .map(AnyMetric::Weighted)
+                .ok_or_else(|| {
+                    format!(
+                        "weights must be two positive integers, like weighted:2,1, not {:?}",
+                        s
+                    )
+                }),
+            _ => Err(format!(
+                "unknown metric {:?}: expected manhattan, chebyshev, euclidean or weighted:<x>,<y>",
+                s
+            )),
+        }
+    }
+}
+
+/// The number of squares in `bounds`, or an error if there are more than
+/// `MAX_CELLS` to look at.
+fn cells_in(bounds: &Rect) -> Result<usize> {
+    bounds.checked_cells(MAX_CELLS).ok_or_else(|| {
+        Error::no_solution(format!(
+            "the coordinates are too far apart: {}x{} squares is too many",
+            bounds.width(),
+            bounds.height()
+        ))
+    })
+}
+
+/// Which coordinate each square of a grid is closest to.
+#[derive(Debug, PartialEq)]
+struct Areas {
+    bounds: Rect,
+    /// Row by row, the index in `coords` of the closest coordinate, or `None`
+    /// where two or more are equally close.
+    owners: Vec<Option<usize>>,
+}
+
+impl Areas {
+    fn new<M: Metric>(coords: &[Point], bounds: &Rect, metric: &M) -> Result<Areas> {
+        let len = cells_in(bounds)?;
+        Ok(match metric.moves() {
+            Some(moves) => Areas::flood(coords, bounds, len, &moves),
+            None => Areas {
+                bounds: *bounds,
+                owners: KdTree::new(coords)
+                    .owners(bounds.points(), metric)
+                    .map(|owner| owner.map(|n| n.index))
+                    .collect(),
+            },
+        })
+    }
+
+    /// Where `p` is in a row by row grid of `bounds`. `p` must be within
+    /// `bounds`, and `bounds` no bigger than `cells_in` allows.
+    fn index(bounds: &Rect, p: &Point) -> usize {
+        let offset = |a: i32, min: i32| (i64::from(a) - i64::from(min)) as usize;
+        offset(p.y, bounds.min.y) * offset(bounds.max.x, bounds.min.x) + offset(p.x, bounds.min.x)
+    }
+
+    /// Floods out from every coordinate at once, nearest squares first, so
+    /// each square is reached from the squares one move closer to its
+    /// closest coordinates. It's shared if they were shared, or if they were
+    /// closest to different coordinates.
+    ///
+    /// When every move costs the same, squares come off a plain queue in
+    /// order of distance. Otherwise, a heap puts the cheapest first.
+    fn flood(coords: &[Point], bounds: &Rect, len: usize, moves: &[(Vector, i64)]) -> Areas {
+        let mut dists = vec![i64::MAX; len];
+        let mut owners = vec![None; dists.len()];
+        let mut sources = vec![];
+        for (i, c) in coords.iter().enumerate() {
+            if !bounds.contains(c) {
                 continue;
             }
+            let index = Areas::index(bounds, c);
+            owners[index] = if dists[index] == 0 { None } else { Some(i) };
+            dists[index] = 0;
+            sources.push(*c);
+        }
 
-            areas
-                .entry(*closest_coords[0])
-                .and_modify(|s: &mut HashSet<Point>| {
-                    s.insert(p);
-                }).or_insert_with(|| {
-                    let mut s = HashSet::new();
-                    s.insert(p);
-                    s
+        let mut step = |p: &Point, dist: i64, reached: &mut dyn FnMut(Point, i64)| {
+            let index = Areas::index(bounds, p);
+            // The heap can hold a square again once a cheaper way to it turns
+            // up, leaving the old entry behind.
+            if dist > dists[index] {
+                return;
+            }
+            for &(step, cost) in moves {
+                let next = match p.checked_add(step) {
+                    Some(next) if bounds.contains(&next) => next,
+                    _ => continue,
+                };
+                let next_index = Areas::index(bounds, &next);
+                let next_dist = dist + cost;
+                if next_dist < dists[next_index] {
+                    dists[next_index] = next_dist;
+                    owners[next_index] = owners[index];
+                    reached(next, next_dist);
+                } else if next_dist == dists[next_index] && owners[next_index] != owners[index] {
+                    owners[next_index] = None;
+                }
+            }
+        };
+
+        if moves.windows(2).all(|pair| pair[0].1 == pair[1].1) {
+            let mut queue: VecDeque<(Point, i64)> = sources.into_iter().map(|c| (c, 0)).collect();
+            while let Some((p, dist)) = queue.pop_front() {
+                step(&p, dist, &mut |next, next_dist| {
+                    queue.push_back((next, next_dist))
+                });
+            }
+        } else {
+            let mut heap: BinaryHeap<_> = sources.into_iter().map(|c| Reverse((0, c))).collect();
+            while let Some(Reverse((dist, p))) = heap.pop() {
+                step(&p, dist, &mut |next, next_dist| {
+                    heap.push(Reverse((next_dist, next)))
                 });
+            }
+        }
+
+        Areas {
+            bounds: *bounds,
+            owners,
+        }
+    }
+
+    fn owner(&self, p: &Point) -> Option<usize> {
+        if self.bounds.contains(p) {
+            self.owners[Areas::index(&self.bounds, p)]
+        } else {
+            None
         }
     }
 
-    areas
+    /// The number of squares closest to each coordinate, or `None` for the
+    /// areas that reach the edge of the grid.
+    fn finite_sizes(&self, coords: &[Point]) -> Vec<Option<usize>> {
+        let mut sizes = vec![Some(0); coords.len()];
+        for &owner in self.owners.iter() {
+            if let Some(Some(size)) = owner.map(|i| sizes[i].as_mut()) {
+                *size += 1;
+            }
+        }
+        for p in self.bounds.points().filter(|p| self.bounds.on_edge(p)) {
+            if let Some(i) = self.owner(&p) {
+                sizes[i] = None;
+            }
+        }
+        sizes
+    }
 }
 
-fn filter_finite(
-    areas: HashMap<Point, HashSet<Point>>,
-    max_point: &Point,
-) -> HashMap<Point, HashSet<Point>> {
-    areas
-        .into_iter()
-        .filter(|(_coord, closest_points)| {
-            let touches_edge = closest_points
-                .iter()
-                .any(|p| p.x == 0 || p.x == max_point.x || p.y == 0 || p.y == max_point.y);
-            !touches_edge
-        }).collect()
+/// A coordinate found by a `KdTree` query, and how far it is from the square
+/// asked about. They sort closest first, and then by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Neighbour {
+    pub dist: i64,
+    pub index: usize,
 }
 
-fn largest_area(areas: HashMap<Point, HashSet<Point>>) -> Option<(Point, usize)> {
-    areas
-        .iter()
-        .map(|(coord, area)| (*coord, area.len()))
-        .max_by(|(_, size1), (_, size2)| size1.cmp(&size2))
+/// A k-d tree of coordinates, for finding the ones closest to a square
+/// without measuring the distance to every one of them.
+///
+/// Coordinates are identified by their index in the slice the tree was built
+/// from. Queries work with any `Metric` where moving a square closer to
+/// another along one axis never takes it further away, which all of ours
+/// are.
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    /// Each subtree is a run of nodes with its root in the middle, split on
+    /// `x` at even depths and on `y` at odd ones. The nodes before the root
+    /// are no further along that axis than it, and the nodes after it are no
+    /// nearer.
+    nodes: Vec<(Point, usize)>,
 }
 
-fn max_point(coords: &[Point]) -> Option<Point> {
-    let max_x = coords.iter().max_by(|p1, p2| p1.x.cmp(&p2.x)).map(|p| p.x);
-    let max_y = coords.iter().max_by(|p1, p2| p1.y.cmp(&p2.y)).map(|p| p.y);
-    max_x.and_then(|x| max_y.map(|y| Point { x, y }))
+/// The coordinate of `p` that a `KdTree` splits on at `depth`.
+fn axis(p: &Point, depth: usize) -> i32 {
+    if depth.is_multiple_of(2) {
+        p.x
+    } else {
+        p.y
+    }
 }
 
-fn part_1(coords: &[Point]) -> Option<usize> {
-    max_point(&coords).and_then(|max_point| {
-        let areas = calc_areas(&coords, &max_point);
-        let finite_areas = filter_finite(areas, &max_point);
-        largest_area(finite_areas).map(|(_point, size)| size)
-    })
+/// What a `KdTree` search is looking for.
+trait Collector {
+    /// The furthest a coordinate can be and still be wanted.
+    fn bound(&self) -> i64;
+    fn offer(&mut self, neighbour: Neighbour);
 }
 
-fn safe_area(coords: &[Point], max_point: &Point, safe_distance: u32) -> HashSet<Point> {
-    let mut area = HashSet::new();
+/// Every coordinate at the smallest distance.
+struct Tied<'a>(&'a mut Vec<Neighbour>);
 
-    for x in 0..max_point.x + 1 {
-        for y in 0..max_point.y + 1 {
-            let p = Point { x, y };
-            let total_dist = coords
-                .iter()
-                .fold(0, |sum, coord| sum + manhattan_dist(&p, coord));
-            if total_dist < safe_distance {
-                area.insert(p);
-            }
+impl<'a> Collector for Tied<'a> {
+    fn bound(&self) -> i64 {
+        self.0.first().map_or(i64::MAX, |n| n.dist)
+    }
+
+    fn offer(&mut self, neighbour: Neighbour) {
+        if neighbour.dist < self.bound() {
+            self.0.clear();
+        }
+        if neighbour.dist <= self.bound() {
+            self.0.push(neighbour);
         }
     }
+}
 
-    area
+/// The `k` closest coordinates, with the furthest of them on top.
+struct Closest {
+    k: usize,
+    heap: BinaryHeap<Neighbour>,
 }
 
-fn part_2(coords: &[Point]) -> Option<usize> {
-    max_point(&coords).map(|max_point| {
-        safe_area(&coords, &max_point, 10000).len()
-    })
+impl Collector for Closest {
+    fn bound(&self) -> i64 {
+        match self.heap.peek() {
+            Some(furthest) if self.heap.len() == self.k => furthest.dist,
+            _ => i64::MAX,
+        }
+    }
+
+    fn offer(&mut self, neighbour: Neighbour) {
+        if self.heap.len() < self.k {
+            self.heap.push(neighbour);
+        } else if self
+            .heap
+            .peek()
+            .is_some_and(|&furthest| neighbour < furthest)
+        {
+            self.heap.pop();
+            self.heap.push(neighbour);
+        }
+    }
 }
 
-fn point<I>() -> impl Parser<Input = I, Output = Point>
-where
-    I: combine::Stream<Item = char> + combine::RangeStreamOnce,
-    I::Range: combine::stream::Range + combine::combinator::StrLike,
-    I::Error: combine::ParseError<I::Item, I::Range, I::Position>,
-{
-    let digits_u32 = || {
-        combine::from_str(combine::parser::range::take_while1(|c: char| {
-            c.is_digit(10)
-        }))
+impl KdTree {
+    pub fn new(coords: &[Point]) -> KdTree {
+        let mut nodes: Vec<(Point, usize)> = coords.iter().cloned().zip(0..).collect();
+        KdTree::build(&mut nodes, 0);
+        KdTree { nodes }
+    }
+
+    fn build(nodes: &mut [(Point, usize)], depth: usize) {
+        if nodes.len() <= 1 {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        nodes.select_nth_unstable_by_key(mid, |&(p, _)| axis(&p, depth));
+        let (before, after) = nodes.split_at_mut(mid);
+        KdTree::build(before, depth + 1);
+        KdTree::build(&mut after[1..], depth + 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Offers `found` each coordinate in `nodes` that could be within its
+    /// bound of `p`. The far side of a split is only searched if the split
+    /// itself is within the bound.
+    fn search<M: Metric, C: Collector>(
+        nodes: &[(Point, usize)],
+        depth: usize,
+        p: &Point,
+        metric: &M,
+        found: &mut C,
+    ) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let (split, index) = nodes[mid];
+        found.offer(Neighbour {
+            dist: metric.dist(p, &split),
+            index,
+        });
+
+        let (before, after) = (&nodes[..mid], &nodes[mid + 1..]);
+        let (near, far) = if axis(p, depth) < axis(&split, depth) {
+            (before, after)
+        } else {
+            (after, before)
+        };
+        KdTree::search(near, depth + 1, p, metric, found);
+
+        let on_split = if depth.is_multiple_of(2) {
+            Point::new(split.x, p.y)
+        } else {
+            Point::new(p.x, split.y)
+        };
+        if metric.dist(p, &on_split) <= found.bound() {
+            KdTree::search(far, depth + 1, p, metric, found);
+        }
+    }
+
+    /// The coordinate closest to `p`, or the lowest indexed of them if
+    /// there's a tie. `None` if the tree is empty.
+    pub fn nearest<M: Metric>(&self, p: &Point, metric: &M) -> Option<Neighbour> {
+        self.k_nearest(p, 1, metric).pop()
+    }
+
+    /// The `k` coordinates closest to `p`, closest first. Ties are broken by
+    /// index.
+    pub fn k_nearest<M: Metric>(&self, p: &Point, k: usize, metric: &M) -> Vec<Neighbour> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut closest = Closest {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        };
+        KdTree::search(&self.nodes, 0, p, metric, &mut closest);
+        closest.heap.into_sorted_vec()
+    }
+
+    /// Every coordinate as close to `p` as the closest one, by index.
+    pub fn all_nearest<M: Metric>(&self, p: &Point, metric: &M) -> Vec<Neighbour> {
+        let mut tied = vec![];
+        KdTree::search(&self.nodes, 0, p, metric, &mut Tied(&mut tied));
+        tied.sort();
+        tied
+    }
+
+    /// The coordinate that owns `p` in the puzzle's sense: the closest one,
+    /// unless two or more are equally close.
+    pub fn owner<M: Metric>(&self, p: &Point, metric: &M) -> Option<Neighbour> {
+        self.owner_using(p, metric, &mut vec![])
+    }
+
+    fn owner_using<M: Metric>(
+        &self,
+        p: &Point,
+        metric: &M,
+        tied: &mut Vec<Neighbour>,
+    ) -> Option<Neighbour> {
+        tied.clear();
+        KdTree::search(&self.nodes, 0, p, metric, &mut Tied(tied));
+        match tied.as_slice() {
+            [owner] => Some(*owner),
+            _ => None,
+        }
+    }
+
+    /// The owner of each of `points` in turn, as with `owner`, computed as
+    /// the iterator is consumed.
+    pub fn owners<'a, I, M>(
+        &'a self,
+        points: I,
+        metric: &'a M,
+    ) -> impl Iterator<Item = Option<Neighbour>> + 'a
+    where
+        I: IntoIterator<Item = Point>,
+        I::IntoIter: 'a,
+        M: Metric,
+    {
+        let mut tied = vec![];
+        points
+            .into_iter()
+            .map(move |p| self.owner_using(&p, metric, &mut tied))
+    }
+}
+
+/// The smallest grid holding all of the coordinates, or `None` if there
+/// aren't any. An error if its edges would be past the largest coordinates.
+fn bounds(coords: &[Point]) -> Result<Option<Rect>> {
+    if coords.is_empty() {
+        return Ok(None);
+    }
+    Rect::checked_bounding(coords)
+        .map(Some)
+        .ok_or_else(too_far_out)
+}
+
+/// The error for a grid with edges past the largest coordinates.
+fn too_far_out() -> Error {
+    Error::no_solution("the coordinates are too far out to hold in a grid")
+}
+
+/// `bounds` with `margin` more squares on every side, or an error if that
+/// goes past the largest coordinates.
+fn expand(bounds: &Rect, margin: i32) -> Result<Rect> {
+    bounds.checked_expanded(margin).ok_or_else(too_far_out)
+}
+
+/// The size of each coordinate's area, or `None` if it goes on forever.
+/// Returns `None` altogether if there are no coordinates, and an error if
+/// they're too far apart to look at every square between them.
+///
+/// For `Manhattan` and `Weighted`, the infinite areas are the ones that reach
+/// the edge of `bounds`. For other metrics, areas can reach the edge and then
+/// narrow to nothing further out, and finite areas can poke out beyond it. So
+/// look in a wider and wider margin around `bounds` until the finite areas
+/// stop changing.
+fn finite_sizes<M: Metric>(coords: &[Point], metric: &M) -> Result<Option<Vec<Option<usize>>>> {
+    let bounds = match bounds(coords)? {
+        Some(bounds) => bounds,
+        None => return Ok(None),
+    };
+    let finite_in = |bounds: &Rect| -> Result<_> {
+        Ok(Areas::new(coords, bounds, metric)?.finite_sizes(coords))
     };
+    if metric.uniform_outside() {
+        return finite_in(&bounds).map(Some);
+    }
+
+    let mut margin = (bounds.width().max(bounds.height()) / 2).max(1);
+    let mut finite = finite_in(&expand(&bounds, margin)?)?;
+    loop {
+        margin *= 2;
+        let wider = finite_in(&expand(&bounds, margin)?)?;
+        if wider == finite {
+            return Ok(Some(finite));
+        }
+        finite = wider;
+    }
+}
+
+fn part_1<M: Metric>(coords: &[Point], metric: &M) -> Result<Option<usize>> {
+    Ok(finite_sizes(coords, metric)?.and_then(|sizes| sizes.into_iter().flatten().max()))
+}
+
+fn total_dist<M: Metric>(coords: &[Point], p: &Point, metric: &M) -> i64 {
+    coords
+        .iter()
+        .fold(0, |sum, coord| sum + metric.dist(p, coord))
+}
+
+/// The number of squares of `bounds` with a total distance under
+/// `safe_distance`, or an error if there are too many squares to look at.
+fn safe_squares_in<M: Metric>(
+    coords: &[Point],
+    bounds: &Rect,
+    metric: &M,
+    safe_distance: i64,
+) -> Result<usize> {
+    cells_in(bounds)?;
+    Ok(bounds
+        .points()
+        .filter(|p| total_dist(coords, p, metric) < safe_distance)
+        .count())
+}
 
-    struct_parser!{
+/// The totals of the `weight`ed distances along one axis to `values`, for
+/// every position from `within.0` up to `within.1` where the total is under
+/// `limit`.
+///
+/// The total is smallest at the median, or at the nearest end of `within`,
+/// and grows steadily either side, so the positions are one run out from
+/// there.
+fn axis_totals(mut values: Vec<i64>, weight: i64, limit: i64, within: (i64, i64)) -> Vec<i64> {
+    values.sort_unstable();
+    let total_at = |x: i64| {
+        values.iter().fold(0i64, |total, &v| {
+            total.saturating_add((x - v).abs().saturating_mul(weight))
+        })
+    };
+    let (lo, hi) = within;
+    let start = values[values.len() / 2].max(lo).min(hi - 1);
+    let mut totals = vec![];
+    for run in [(start, 1), (start - 1, -1)].iter() {
+        let (mut x, step) = *run;
+        while lo <= x && x < hi {
+            let total = total_at(x);
+            if total >= limit {
+                break;
+            }
+            totals.push(total);
+            x += step;
+        }
+    }
+    totals
+}
+
+/// The grid to look for the safe region in when the total distance doesn't
+/// split by axis, or `None` if there are no coordinates. An error if it
+/// gets too big.
+///
+/// The total distance is lowest somewhere within the coordinates' bounds and
+/// grows steadily away from there, so the region is one convex blob. Once no
+/// square around the edge of the grid is safe, the grid holds all of it.
+fn safe_bounds<M: Metric>(
+    coords: &[Point],
+    metric: &M,
+    safe_distance: i64,
+    search: Search,
+) -> Result<Option<Rect>> {
+    let mut bounds = match bounds(coords)? {
+        Some(bounds) => bounds,
+        None => return Ok(None),
+    };
+    if search == Search::Everywhere {
+        let mut margin = 1;
+        while bounds
+            .points()
+            .filter(|p| bounds.on_edge(p))
+            .any(|p| total_dist(coords, &p, metric) < safe_distance)
+        {
+            bounds = expand(&bounds, margin)?;
+            if bounds.checked_cells(MAX_CELLS).is_none() {
+                return Err(Error::no_solution("the safe region is too large to search"));
+            }
+            margin *= 2;
+        }
+    }
+    Ok(Some(bounds))
+}
+
+/// The number of squares with a total distance under `safe_distance`,
+/// within the coordinates' bounds or anywhere. `None` if there are no
+/// coordinates.
+///
+/// When the total splits into one along each axis, it counts the safe
+/// squares row by row: a square is safe if its `x` total is under
+/// `safe_distance` less its `y` total. Otherwise it looks at every square of
+/// `safe_bounds`.
+fn safe_region_size<M: Metric>(
+    coords: &[Point],
+    metric: &M,
+    safe_distance: i64,
+    search: Search,
+) -> Result<Option<usize>> {
+    let (x_weight, y_weight) = match (coords.is_empty(), metric.axis_weights()) {
+        (true, _) => return Ok(None),
+        (false, Some(weights)) => weights,
+        (false, None) => {
+            return match safe_bounds(coords, metric, safe_distance, search)? {
+                Some(bounds) => safe_squares_in(coords, &bounds, metric, safe_distance).map(Some),
+                None => Ok(None),
+            };
+        }
+    };
+
+    let axis = |value: fn(&Point) -> i32, weight: i64| {
+        let values: Vec<i64> = coords.iter().map(|c| i64::from(value(c))).collect();
+        let within = match search {
+            Search::Bounds => (
+                *values.iter().min().unwrap(),
+                *values.iter().max().unwrap() + 1,
+            ),
+            Search::Everywhere => (i64::MIN, i64::MAX),
+        };
+        axis_totals(values, weight, safe_distance, within)
+    };
+    let x_totals = axis(|p| p.x, x_weight);
+    let mut y_totals = axis(|p| p.y, y_weight);
+    y_totals.sort_unstable();
+    Ok(Some(
+        x_totals
+            .iter()
+            .map(|&x_total| y_totals.partition_point(|&y_total| y_total < safe_distance - x_total))
+            .sum(),
+    ))
+}
+
+fn part_2<M: Metric>(coords: &[Point], metric: &M, search: Search) -> Result<Option<usize>> {
+    safe_region_size(coords, metric, 10000, search)
+}
+
+/// Each coordinate's area in its own color, dimmed if it's infinite, with the
+/// safe region lightened and the coordinates themselves in white.
+fn render_areas<M: Metric>(
+    coords: &[Point],
+    bounds: &Rect,
+    metric: &M,
+    safe_distance: i64,
+) -> Result<Image> {
+    let areas = Areas::new(coords, bounds, metric)?;
+    let colors: Vec<Color> = finite_sizes(coords, metric)?
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, size)| match size {
+            Some(_) => render::region_color(i),
+            None => render::region_color(i).lerp(Color::BLACK, 0.6),
+        })
+        .collect();
+    let points: HashSet<&Point> = coords.iter().collect();
+
+    Ok(Image::from_fn(
+        bounds.width() as usize,
+        bounds.height() as usize,
+        |x, y| {
+            let p = bounds.min + Vector::new(x as i32, y as i32);
+            let color = areas.owner(&p).map_or(Color::BLACK, |i| colors[i]);
+            if points.contains(&p) {
+                Color::WHITE
+            } else if total_dist(coords, &p, metric) < safe_distance {
+                color.lerp(Color::WHITE, 0.5)
+            } else {
+                color
+            }
+        },
+    ))
+}
+
+fn point<'a>() -> impl Parser<Input = parse::Stream<'a>, Output = Point> {
+    struct_parser! {
         Point {
-            x: digits_u32(),
-            _: combine::char::string(", "),
-            y: digits_u32()
+            x: signed(),
+            _: field(tag(",")),
+            y: signed()
         }
     }
 }
@@ -153,47 +891,385 @@ where
 mod test {
     use super::*;
 
+    /// Every coordinate's distance from `p`, closest first.
+    fn scan<M: Metric>(coords: &[Point], p: &Point, metric: &M) -> Vec<Neighbour> {
+        let mut all: Vec<Neighbour> = coords
+            .iter()
+            .enumerate()
+            .map(|(index, c)| Neighbour {
+                dist: metric.dist(p, c),
+                index,
+            })
+            .collect();
+        all.sort();
+        all
+    }
+
+    /// The index of the coordinate closest to `p`, unless there's a tie.
+    fn nearest<M: Metric>(coords: &[Point], p: &Point, metric: &M) -> Option<usize> {
+        match scan(coords, p, metric).as_slice() {
+            [a, b, ..] if a.dist == b.dist => None,
+            [a, ..] => Some(a.index),
+            [] => None,
+        }
+    }
+
     fn get_example_coords() -> Vec<Point> {
         vec![
-            Point { x: 1, y: 1 },
-            Point { x: 1, y: 6 },
-            Point { x: 8, y: 3 },
-            Point { x: 3, y: 4 },
-            Point { x: 5, y: 5 },
-            Point { x: 8, y: 9 },
+            Point::new(1, 1),
+            Point::new(1, 6),
+            Point::new(8, 3),
+            Point::new(3, 4),
+            Point::new(5, 5),
+            Point::new(8, 9),
         ]
     }
 
     #[test]
-    fn test_calc_areas() {
+    fn test_areas() {
         let coords = get_example_coords();
-        let max_point = max_point(&coords).unwrap();
+        let bounds = bounds(&coords).unwrap().unwrap();
+
+        let areas = Areas::new(&coords, &bounds, &Manhattan).unwrap();
+        assert_eq!(areas.owner(&Point::new(2, 1)), Some(0));
+        assert_eq!(areas.owner(&Point::new(2, 5)), None);
+        assert_eq!(areas.owner(&Point::new(0, 0)), None);
+
+        assert_eq!(
+            areas.finite_sizes(&coords),
+            vec![None, None, None, Some(9), Some(17), None]
+        );
 
-        let areas = calc_areas(&coords, &max_point);
+        assert_eq!(part_1(&coords, &Manhattan).unwrap(), Some(17));
+    }
 
-        let finite_areas = filter_finite(areas, &max_point);
+    #[test]
+    fn test_flood_matches_scan() {
+        let mut coords = get_example_coords();
+        // Two coordinates in the same place share their area.
+        coords.push(Point::new(8, 9));
+        let bounds = bounds(&coords).unwrap().unwrap().expanded(3);
+        let metrics = [
+            AnyMetric::Manhattan(Manhattan),
+            AnyMetric::Chebyshev(Chebyshev),
+            AnyMetric::Weighted(Weighted { x: 2, y: 3 }),
+        ];
+        for metric in metrics.iter() {
+            let scanned = bounds
+                .points()
+                .map(|p| nearest(&coords, &p, metric))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                Areas::new(&coords, &bounds, metric).unwrap().owners,
+                scanned,
+                "{:?}",
+                metric
+            );
+        }
+    }
+
+    #[test]
+    fn test_kd_tree() {
+        let mut coords = get_example_coords();
+        coords.push(Point::new(8, 9));
+        coords.extend((0..40).map(|i| Point::new(i * 7 % 23 - 5, i * 11 % 17 - 3)));
+        let tree = KdTree::new(&coords);
+        assert_eq!(tree.len(), coords.len());
+
+        let bounds = bounds(&coords).unwrap().unwrap().expanded(4);
+        let metrics = [
+            AnyMetric::Manhattan(Manhattan),
+            AnyMetric::Chebyshev(Chebyshev),
+            AnyMetric::EuclideanSquared(EuclideanSquared),
+            AnyMetric::Weighted(Weighted { x: 1, y: 4 }),
+        ];
+        for metric in metrics.iter() {
+            for p in bounds.points() {
+                let all = scan(&coords, &p, metric);
+                let tied: Vec<Neighbour> = all
+                    .iter()
+                    .cloned()
+                    .take_while(|n| n.dist == all[0].dist)
+                    .collect();
+                assert_eq!(tree.all_nearest(&p, metric), tied, "{:?} {:?}", metric, p);
+                assert_eq!(tree.nearest(&p, metric), Some(all[0]));
+                assert_eq!(tree.k_nearest(&p, 5, metric), &all[..5]);
+                assert_eq!(
+                    tree.owner(&p, metric).map(|n| n.index),
+                    nearest(&coords, &p, metric)
+                );
+            }
+            let owners: Vec<Option<Neighbour>> = tree.owners(bounds.points(), metric).collect();
+            let expected: Vec<Option<Neighbour>> =
+                bounds.points().map(|p| tree.owner(&p, metric)).collect();
+            assert_eq!(owners, expected);
+        }
+
+        // F is there twice, so it has no owner.
+        let tree = KdTree::new(&coords[..7]);
+        let p = Point::new(8, 9);
+        assert_eq!(tree.owner(&p, &Manhattan), None);
+        assert_eq!(
+            tree.all_nearest(&p, &Manhattan),
+            vec![
+                Neighbour { dist: 0, index: 5 },
+                Neighbour { dist: 0, index: 6 }
+            ]
+        );
+        assert_eq!(tree.k_nearest(&p, 100, &Manhattan).len(), 7);
+        assert_eq!(tree.k_nearest(&p, 0, &Manhattan), vec![]);
+
+        let empty = KdTree::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.nearest(&p, &Manhattan), None);
+        assert_eq!(empty.all_nearest(&p, &Manhattan), vec![]);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let (a, b) = (Point::new(1, 1), Point::new(-2, 5));
+        assert_eq!(Manhattan.dist(&a, &b), 7);
+        assert_eq!(Chebyshev.dist(&a, &b), 4);
+        assert_eq!(EuclideanSquared.dist(&a, &b), 25);
+        assert_eq!(Weighted { x: 2, y: 1 }.dist(&a, &b), 10);
 
+        assert_eq!("chebyshev".parse(), Ok(AnyMetric::Chebyshev(Chebyshev)));
         assert_eq!(
-            finite_areas.keys().collect::<HashSet<&Point>>(),
-            vec![coords[3], coords[4]].iter().collect()
+            "weighted:3, 1".parse(),
+            Ok(AnyMetric::Weighted(Weighted { x: 3, y: 1 }))
         );
+        assert!("weighted:0,1".parse::<AnyMetric>().is_err());
+        assert!("weighted:1".parse::<AnyMetric>().is_err());
+        assert!("taxicab".parse::<AnyMetric>().is_err());
+    }
 
-        assert_eq!(largest_area(finite_areas), Some((coords[4], 17)));
+    #[test]
+    fn test_finite_areas_per_metric() {
+        let coords = get_example_coords();
+        let metrics = [
+            AnyMetric::Manhattan(Manhattan),
+            AnyMetric::Chebyshev(Chebyshev),
+            AnyMetric::EuclideanSquared(EuclideanSquared),
+            AnyMetric::Weighted(Weighted { x: 1, y: 3 }),
+        ];
+        for metric in metrics.iter() {
+            // Looking much further out finds the same finite areas.
+            let bounds = bounds(&coords).unwrap().unwrap().expanded(50);
+            assert_eq!(
+                finite_sizes(&coords, metric).unwrap(),
+                Some(
+                    Areas::new(&coords, &bounds, metric)
+                        .unwrap()
+                        .finite_sizes(&coords)
+                ),
+                "{:?}",
+                metric
+            );
+        }
+        assert_eq!(part_1(&coords, &Chebyshev).unwrap(), Some(10));
+        assert_eq!(part_1(&coords, &EuclideanSquared).unwrap(), Some(16));
 
-        assert_eq!(part_1(&coords), Some(17));
+        // D sits above a flat triangle, so C's area is finite, but reaches
+        // well below all of them.
+        let coords = [
+            Point::new(0, 0),
+            Point::new(20, 0),
+            Point::new(10, 3),
+            Point::new(10, 10),
+        ];
+        let sizes = finite_sizes(&coords, &EuclideanSquared).unwrap().unwrap();
+        assert_eq!(sizes.iter().flatten().count(), 1);
+        assert!(sizes[2].is_some());
+        let areas = Areas::new(
+            &coords,
+            &Rect::bounding(&coords).unwrap().expanded(20),
+            &EuclideanSquared,
+        )
+        .unwrap();
+        assert_eq!(areas.owner(&Point::new(10, -12)), Some(2));
+    }
+
+    #[test]
+    fn test_set_option() {
+        let mut coords = Day6::parse("1, 1\n1, 6\n8, 3\n3, 4\n5, 5\n8, 9").unwrap();
+        let setting = |s: &str| s.parse::<Setting>().unwrap();
+        Day6::set_option(&mut coords, &setting("metric=euclidean")).unwrap();
+        assert_eq!(coords.metric, AnyMetric::EuclideanSquared(EuclideanSquared));
+        assert_eq!(
+            Day6::set_option(&mut coords, &setting("metric=taxicab"))
+                .unwrap_err()
+                .to_string(),
+            "bad option: unknown metric \"taxicab\": expected manhattan, chebyshev, euclidean or weighted:<x>,<y>"
+        );
+        assert!(Day6::set_option(&mut coords, &setting("speed=1")).is_err());
+
+        Day6::set_option(&mut coords, &setting("search=everywhere")).unwrap();
+        assert_eq!(coords.search, Search::Everywhere);
+        assert!(Day6::set_option(&mut coords, &setting("search=far")).is_err());
     }
 
     #[test]
     fn test_point_parser() {
-        assert_eq!(point().easy_parse("1, 2"), Ok((Point { x: 1, y: 2 }, "")));
+        assert_eq!(point().easy_parse("1, 2"), Ok((Point::new(1, 2), "")));
+        assert_eq!(point().easy_parse("-1, -20"), Ok((Point::new(-1, -20), "")));
     }
 
     #[test]
-    fn test_safe_area() {
+    fn test_bounds() {
+        // Far from the origin, the grid is just big enough for the
+        // coordinates, and the areas come out the same as anywhere else.
         let coords = get_example_coords();
-        let max_point = max_point(&coords).unwrap();
+        let offset = Vector::new(-1000, 500);
+        let moved: Vec<Point> = coords.iter().map(|&c| c + offset).collect();
+        assert_eq!(
+            bounds(&moved).unwrap(),
+            Some(Rect::new(Point::new(-999, 501), Point::new(-991, 510)))
+        );
+        assert_eq!(
+            finite_sizes(&moved, &Manhattan).unwrap(),
+            finite_sizes(&coords, &Manhattan).unwrap()
+        );
+    }
 
-        assert_eq!(safe_area(&coords, &max_point, 32).len(), 16);
+    #[test]
+    fn test_coords_far_apart() {
+        // Too many squares for an i32, and too many to look at even when
+        // they'd fit.
+        for input in ["-30000, -30000\n30000, 30000\n0, 5", "0, 0\n20000, 20000"].iter() {
+            let mut coords = Day6::parse(input).unwrap();
+            assert!(Day6::part_1(&coords).is_err());
+            assert!(Day6::render(&coords).is_err());
+            // The safe region is counted without a grid, and there isn't one.
+            assert_eq!(Day6::part_2(&coords).unwrap(), 0);
+            coords.metric = AnyMetric::EuclideanSquared(EuclideanSquared);
+            assert!(Day6::part_1(&coords).is_err());
+            assert!(Day6::part_2(&coords).is_err());
+        }
+        assert_eq!(
+            Day6::part_1(&Day6::parse("0, 0\n20000, 20000").unwrap())
+                .unwrap_err()
+                .to_string(),
+            "no solution: the coordinates are too far apart: 20001x20001 squares is too many"
+        );
+
+        // Grids with edges, or a width, past the largest i32.
+        for &(input, safe) in [("2147483647, 0", 1), ("-2147483648, 0\n2147483000, 0", 0)].iter() {
+            let mut coords = Day6::parse(input).unwrap();
+            assert_eq!(
+                Day6::part_1(&coords).unwrap_err().to_string(),
+                "no solution: the coordinates are too far out to hold in a grid"
+            );
+            assert!(Day6::render(&coords).is_err());
+            assert_eq!(Day6::part_2(&coords).unwrap(), safe);
+            coords.metric = AnyMetric::Chebyshev(Chebyshev);
+            assert!(Day6::part_2(&coords).is_err());
+        }
+        let mut coords = Day6::parse("2147483600, 0").unwrap();
+        coords.search = Search::Everywhere;
+        assert_eq!(
+            Day6::part_2(&coords).unwrap(),
+            2 * 10000 * 10000 - 2 * 10000 + 1
+        );
+        coords.metric = AnyMetric::Chebyshev(Chebyshev);
+        assert!(Day6::part_1(&coords).is_err());
+        assert!(Day6::part_2(&coords).is_err());
+        // One square from the smallest i32, the areas flood without stepping
+        // past it.
+        let coords = Day6::parse("-2147483648, -2147483648\n-2147483646, -2147483647").unwrap();
+        assert_eq!(
+            Day6::part_1(&coords).unwrap_err().to_string(),
+            "no solution: no finite areas"
+        );
     }
 
+    #[test]
+    fn test_safe_region() {
+        let coords = get_example_coords();
+        let bounds = bounds(&coords).unwrap().unwrap();
+        let scan = |rect: &Rect, metric: &AnyMetric, safe_distance: i64| {
+            rect.points()
+                .filter(|p| total_dist(&coords, p, metric) < safe_distance)
+                .count()
+        };
+
+        assert_eq!(
+            safe_region_size(&coords, &Manhattan, 32, Search::Bounds).unwrap(),
+            Some(16)
+        );
+        let metrics = [
+            AnyMetric::Manhattan(Manhattan),
+            AnyMetric::Chebyshev(Chebyshev),
+            AnyMetric::EuclideanSquared(EuclideanSquared),
+            AnyMetric::Weighted(Weighted { x: 2, y: 1 }),
+        ];
+        for metric in metrics.iter() {
+            for &safe_distance in [1, 32, 60, 200].iter() {
+                assert_eq!(
+                    safe_region_size(&coords, metric, safe_distance, Search::Bounds).unwrap(),
+                    Some(scan(&bounds, metric, safe_distance)),
+                    "{:?} {}",
+                    metric,
+                    safe_distance
+                );
+                // No safe square is as far as `safe_distance` from a
+                // coordinate.
+                let around = bounds.expanded(safe_distance as i32);
+                assert_eq!(
+                    safe_region_size(&coords, metric, safe_distance, Search::Everywhere).unwrap(),
+                    Some(scan(&around, metric, safe_distance)),
+                    "{:?} {}",
+                    metric,
+                    safe_distance
+                );
+            }
+        }
+        // A larger safe region spills out past the coordinates.
+        assert!(
+            safe_region_size(&coords, &Manhattan, 60, Search::Everywhere).unwrap()
+                > safe_region_size(&coords, &Manhattan, 60, Search::Bounds).unwrap()
+        );
+        assert_eq!(
+            safe_region_size(&[], &Manhattan, 60, Search::Everywhere).unwrap(),
+            None
+        );
+
+        // Around a single coordinate, every square less than 10000 steps
+        // away.
+        let mut coords = Day6::parse("1, 1").unwrap();
+        coords.search = Search::Everywhere;
+        assert_eq!(
+            Day6::part_2(&coords).unwrap(),
+            2 * 10000 * 10000 - 2 * 10000 + 1
+        );
+        coords.metric = AnyMetric::Chebyshev(Chebyshev);
+        assert_eq!(
+            Day6::part_2(&coords).unwrap_err().to_string(),
+            "no solution: the safe region is too large to search"
+        );
+    }
+
+    #[test]
+    fn test_render_areas() {
+        let coords = get_example_coords();
+        let bounds = bounds(&coords).unwrap().unwrap();
+        let image = render_areas(&coords, &bounds, &Manhattan, 32).unwrap();
+
+        // The image starts at A, in the top left.
+        assert_eq!((image.width, image.height), (8, 9));
+        let pixel = |x: usize, y: usize| image.get(x - 1, y - 1);
+        // (2, 1) is in A's infinite area, (2, 3) is in D's finite area, and
+        // (5, 4) is in E and the safe region.
+        assert_eq!(pixel(1, 1), Some(Color::WHITE));
+        assert_eq!(
+            pixel(2, 1),
+            Some(render::region_color(0).lerp(Color::BLACK, 0.6))
+        );
+        assert_eq!(pixel(2, 3), Some(render::region_color(3)));
+        assert_eq!(
+            pixel(5, 4),
+            Some(render::region_color(4).lerp(Color::WHITE, 0.5))
+        );
+        // (8, 6) is as close to C as to F.
+        assert_eq!(pixel(8, 6), Some(Color::BLACK));
+    }
 }
@@ -1,24 +1,42 @@
+// Written before these lints existed.
+#![allow(clippy::redundant_closure, clippy::len_zero)]
+
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read data file");
-
-    let (steps, _) = parser::parse_steps(&input).expect("Couldn't parse input steps");
+use error::{Error, Result};
+use solution::Solution;
 
-    let mut step_deps = build_step_deps(&steps);
+pub struct Day7;
 
-    let step_order: String = step_order(&mut step_deps).into_iter().collect();
+impl Solution for Day7 {
+    type Input = Vec<(Step, Step)>;
+    type Part1 = String;
+    type Part2 = u32;
 
-    println!("Day 7, part 1: {}", step_order);
+    fn parse(input: &str) -> Result<Vec<(Step, Step)>> {
+        parser::parse_steps(input)
+    }
 
-    let mut step_deps = build_step_deps(&steps);
+    fn part_1(steps: &Vec<(Step, Step)>) -> Result<String> {
+        let mut step_deps = build_step_deps(steps);
+        step_order(&mut step_deps)
+            .map(|steps| steps.into_iter().collect())
+            .ok_or_else(|| Error::no_solution("the steps depend on each other in a cycle"))
+    }
 
-    let part_2_solution = duration(&mut step_deps, 5, 60);
-    println!("Day 7, part 2: {}", part_2_solution);
+    fn part_2(steps: &Vec<(Step, Step)>) -> Result<u32> {
+        let mut step_deps = build_step_deps(steps);
+        let duration = duration(&mut step_deps, 5, 60);
+        if step_deps.is_empty() {
+            Ok(duration)
+        } else {
+            Err(Error::no_solution("the steps depend on each other in a cycle"))
+        }
+    }
 }
 
-type Step = char;
+pub type Step = char;
 
 type StepDeps = HashMap<Step, HashSet<Step>>;
 
@@ -61,18 +79,17 @@ fn do_step(step: Step, step_deps: &mut StepDeps) {
     });
 }
 
-fn step_order(step_deps: &mut StepDeps) -> Vec<Step> {
+/// Returns `None` if some steps can never become available.
+fn step_order(step_deps: &mut StepDeps) -> Option<Vec<Step>> {
     let mut done_steps = Vec::new();
 
     while step_deps.len() > 0 {
-        let mut available_steps = get_available_steps(step_deps);
-        available_steps.sort();
-        let step = available_steps.remove(0);
+        let step = get_available_steps(step_deps).into_iter().min()?;
         done_steps.push(step);
         do_step(step, step_deps);
     }
 
-    done_steps
+    Some(done_steps)
 }
 
 fn step_cost(step: Step, base: u32) -> u32 {
@@ -118,29 +135,26 @@ fn duration(step_deps: &mut StepDeps, workers: usize, base_cost: u32) -> u32 {
 }
 
 mod parser {
-    use combine::parser::char::{newline, string, upper};
-    use combine::sep_by;
+    use combine::parser::char::upper;
     use combine::Parser;
 
+    use error::Result;
+    use parse::{self, tag};
+
     // Step P must be finished before step O can begin.
-    pub fn parse_steps(
-        input: &str,
-    ) -> Result<
-        (Vec<(char, char)>, &str),
-        combine::easy::Errors<char, &str, combine::stream::PointerOffset>,
-    > {
+    pub fn parse_steps(input: &str) -> Result<Vec<(char, char)>> {
         let step_line = || {
             (
-                string("Step "),
+                tag("Step "),
                 upper(),
-                string(" must be finished before step "),
+                tag(" must be finished before step "),
                 upper(),
-                string(" can begin."),
+                tag(" can begin."),
             )
                 .map(|t| (t.1, t.3))
         };
 
-        sep_by(step_line(), newline()).easy_parse(input)
+        parse::lines_of(input, step_line())
     }
 }
 
@@ -183,8 +197,11 @@ mod test {
 
         assert_eq!(
             step_order(&mut step_deps),
-            vec!['C', 'A', 'B', 'D', 'F', 'E']
+            Some(vec!['C', 'A', 'B', 'D', 'F', 'E'])
         );
+
+        let mut cyclic_deps = build_step_deps(&[('A', 'B'), ('B', 'A')]);
+        assert_eq!(step_order(&mut cyclic_deps), None);
     }
 
     #[test]
@@ -1,20 +1,31 @@
-use combine::{count, easy::Stream, token, Parser};
+use combine::{count, token, Parser};
 
-pub fn run(path: &str) {
-    let input = std::fs::read_to_string(path).expect("Couldn't read input file");
+use error::Result;
+use parse::{self, unsigned, Stream};
+use solution::Solution;
 
-    let node = parse_node()
-        .easy_parse(&input)
-        .expect("Couldn't parse nodes")
-        .0;
+pub struct Day8;
 
-    println!("Day 8, part 1: {}", node.sum_metadatas());
+impl Solution for Day8 {
+    type Input = Node;
+    type Part1 = u32;
+    type Part2 = u32;
 
-    println!("Day 8, part 2: {}", node.value());
+    fn parse(input: &str) -> Result<Node> {
+        parse::parse_all(input, parse_node())
+    }
+
+    fn part_1(node: &Node) -> Result<u32> {
+        Ok(node.sum_metadatas())
+    }
+
+    fn part_2(node: &Node) -> Result<u32> {
+        Ok(node.value())
+    }
 }
 
 #[derive(Debug, PartialEq)]
-struct Node {
+pub struct Node {
     children: Vec<Node>,
     metadata: Vec<u32>,
 }
@@ -26,14 +37,15 @@ impl Node {
     }
 
     fn value(&self) -> u32 {
-        if self.children.len() == 0 {
+        if self.children.is_empty() {
             self.sum_metadatas()
         } else {
             self.metadata
                 .iter()
                 .map(|&i| {
-                    self.children
-                        .get((i as usize) - 1)
+                    (i as usize)
+                        .checked_sub(1)
+                        .and_then(|i| self.children.get(i))
                         .map(|n| n.value())
                         .unwrap_or(0)
                 }).sum()
@@ -41,20 +53,14 @@ impl Node {
     }
 }
 
-fn digits_u32<'a>() -> impl Parser<Input = Stream<&'a str>, Output = u32> {
-    combine::from_str(combine::parser::range::take_while1(|c: char| {
-        c.is_digit(10)
-    }))
-}
-
-fn parse_node_<'a>() -> impl Parser<Input = Stream<&'a str>, Output = Node> {
-    digits_u32()
+fn parse_node_<'a>() -> impl Parser<Input = Stream<'a>, Output = Node> {
+    unsigned()
         .skip(token(' '))
-        .then(move |num_child_nodes: u32| {
-            digits_u32().then(move |num_metadatas: u32| {
+        .then(move |num_child_nodes: usize| {
+            unsigned().then(move |num_metadatas: usize| {
                 (
-                    count(num_child_nodes as usize, token(' ').with(parse_node())),
-                    count(num_metadatas as usize, token(' ').with(digits_u32())),
+                    count(num_child_nodes, token(' ').with(parse_node())),
+                    count(num_metadatas, token(' ').with(unsigned::<u32>())),
                 )
                     .map(|t| Node {
                         children: t.0,
@@ -65,7 +71,7 @@ fn parse_node_<'a>() -> impl Parser<Input = Stream<&'a str>, Output = Node> {
 }
 
 parser!{
-    fn parse_node['a]()(Stream<&'a str>) -> Node
+    fn parse_node['a]()(Stream<'a>) -> Node
     {
         parse_node_()
     }
@@ -1,53 +1,454 @@
 use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
 
-pub fn run() {
-    let part_1_solution = ::day_4::max_entry(&play(455, 71223));
-    println!("Day 9, part 1: {:?}", part_1_solution);
+use error::{Error, Result};
+use solution::Solution;
 
-    // let part_2_solution = ::day_4::max_entry(&play(455, 71223 * 100));
-    // println!("Day 9, part 2: {:?}", part_2_solution);
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<Game>;
+    type Part1 = Winners;
+    type Part2 = Winners;
+
+    fn parse(input: &str) -> Result<Vec<Game>> {
+        let games = parser::parse_games(input)?;
+        if games.is_empty() {
+            return Err(Error::no_solution("no games given"));
+        }
+        Ok(games)
+    }
+
+    fn part_1(games: &Vec<Game>) -> Result<Winners> {
+        Ok(games
+            .iter()
+            .map(|game| winner(&GameRules::new(game.players), game.last_marble))
+            .collect())
+    }
+
+    fn part_2(games: &Vec<Game>) -> Result<Winners> {
+        games
+            .iter()
+            .map(|game| {
+                let last_marble = game
+                    .last_marble
+                    .checked_mul(100)
+                    .ok_or_else(|| Error::no_solution("too many marbles"))?;
+                Ok(winner(&GameRules::new(game.players), last_marble))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Game {
+    players: u32,
+    last_marble: u32,
 }
 
+/// Players are numbered from 1, and player 1 places marble 1.
 type Player = u32;
-type Score = u32;
-
-fn play(players: u32, last_marble: u32) -> HashMap<Player, Score> {
-    let mut player = 0;
-    let mut circle = vec![0];
-    let mut current_marble_idx = 0;
-    let mut player_scores = HashMap::new();
-
-    for marble in 1..last_marble + 1 {
-        player = (player + 1) % players;
-        if marble % 23 == 0 {
-            current_marble_idx =
-                (current_marble_idx + circle.len() - 7) % circle.len();
-            let removed_marble = circle.remove(current_marble_idx);
-            let added_score = removed_marble + marble;
-            player_scores
-                .entry(player)
-                .and_modify(|score| *score += added_score)
-                .or_insert(added_score);
+pub type Score = u64;
+
+#[derive(Debug, PartialEq)]
+pub struct Winner {
+    /// `None` if no marble was scored, so nobody won.
+    player: Option<Player>,
+    score: Score,
+}
+
+/// The winner of each game, in the order the games were given.
+#[derive(Debug, PartialEq)]
+pub struct Winners(Vec<Winner>);
+
+impl ::std::iter::FromIterator<Winner> for Winners {
+    fn from_iter<I: IntoIterator<Item = Winner>>(iter: I) -> Winners {
+        Winners(iter.into_iter().collect())
+    }
+}
+
+impl fmt::Display for Winners {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, winner) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match winner.player {
+                Some(player) => write!(f, "{} (player {})", winner.score, player)?,
+                None => write!(f, "{} (no winner)", winner.score)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The player with the highest score. Ties go to the lowest-numbered player.
+fn winner(rules: &GameRules, last_marble: Marble) -> Winner {
+    play(rules, last_marble)
+        .into_iter()
+        .max_by(|(p1, s1), (p2, s2)| s1.cmp(s2).then(p2.cmp(p1)))
+        .map(|(player, score)| Winner {
+            player: Some(player),
+            score,
+        })
+        .unwrap_or(Winner {
+            player: None,
+            score: 0,
+        })
+}
+
+fn play(rules: &GameRules, last_marble: Marble) -> HashMap<Player, Score> {
+    let mut game = MarbleGame::with_capacity(rules.clone(), last_marble as usize + 1);
+    while game.next_marble <= last_marble {
+        game.step();
+    }
+    game.scores
+}
+
+pub type Marble = u32;
+
+/// The numbers that make up the game. `GameRules::new` gives the puzzle's
+/// rules for some number of players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRules {
+    pub players: u32,
+    /// Marbles that are a multiple of this are scored rather than placed. 0
+    /// means no marble is ever scored.
+    pub scoring_modulus: Marble,
+    /// How many marbles counter-clockwise of the current one the marble that
+    /// is removed when scoring is.
+    pub removal_offset: usize,
+    /// Where a new marble is placed, counting clockwise from the current one:
+    /// 1 is straight after it, and 2 is between the next two marbles.
+    pub insertion_offset: usize,
+}
+
+impl GameRules {
+    pub fn new(players: u32) -> GameRules {
+        GameRules {
+            players,
+            scoring_modulus: 23,
+            removal_offset: 7,
+            insertion_offset: 2,
+        }
+    }
+}
+
+/// A game in progress, placing one marble per `step`.
+///
+/// The current marble is kept at the back of `circle`, and the front is the
+/// marble clockwise of it, so every move is a short rotation plus a push or pop
+/// at the back.
+pub struct MarbleGame {
+    rules: GameRules,
+    circle: VecDeque<Marble>,
+    next_marble: Marble,
+    scores: HashMap<Player, Score>,
+}
+
+impl MarbleGame {
+    pub fn new(rules: GameRules) -> MarbleGame {
+        MarbleGame::with_capacity(rules, 1)
+    }
+
+    fn with_capacity(rules: GameRules, capacity: usize) -> MarbleGame {
+        let mut circle = VecDeque::with_capacity(capacity);
+        circle.push_back(0);
+        MarbleGame {
+            rules,
+            circle,
+            next_marble: 1,
+            scores: HashMap::new(),
+        }
+    }
+
+    /// The player who placed the last marble, if any have been placed.
+    pub fn last_player(&self) -> Option<Player> {
+        match self.next_marble {
+            1 => None,
+            marble => Some(self.player_for(marble - 1)),
+        }
+    }
+
+    fn player_for(&self, marble: Marble) -> Player {
+        (marble - 1) % self.rules.players.max(1) + 1
+    }
+
+    /// `None` once every marble has been scored.
+    pub fn current_marble(&self) -> Option<Marble> {
+        self.circle.back().cloned()
+    }
+
+    /// The marbles clockwise from marble 0, or from the current marble if 0
+    /// has been scored.
+    pub fn circle(&self) -> Vec<Marble> {
+        let mut circle: Vec<Marble> = self.circle.iter().cloned().collect();
+        let start = circle
+            .iter()
+            .position(|&m| m == 0)
+            .unwrap_or_else(|| circle.len().saturating_sub(1));
+        circle.rotate_left(start);
+        circle
+    }
+
+    pub fn scores(&self) -> &HashMap<Player, Score> {
+        &self.scores
+    }
+
+    /// Plays the next marble.
+    pub fn step(&mut self) {
+        let marble = self.next_marble;
+        let player = self.player_for(marble);
+        self.next_marble += 1;
+
+        // No marble is a multiple of 0, since they start at 1.
+        if marble.is_multiple_of(self.rules.scoring_modulus) {
+            let mut added_score = Score::from(marble);
+            if !self.circle.is_empty() {
+                let len = self.circle.len();
+                self.circle.rotate_right(self.rules.removal_offset % len);
+                added_score += Score::from(self.circle.pop_back().unwrap());
+                if !self.circle.is_empty() {
+                    self.circle.rotate_left(1);
+                }
+            }
+            *self.scores.entry(player).or_insert(0) += added_score;
         } else {
-            current_marble_idx = (current_marble_idx + 2) % circle.len();
-            circle.insert(current_marble_idx, marble);
+            if !self.circle.is_empty() {
+                let len = self.circle.len();
+                let offset = self.rules.insertion_offset % len;
+                self.circle.rotate_left((offset + len - 1) % len);
+            }
+            self.circle.push_back(marble);
         }
     }
 
-    player_scores
+    pub fn state(&self) -> GameState {
+        GameState {
+            player: self.last_player(),
+            current_marble: self.current_marble(),
+            circle: self.circle(),
+            scores: self.scores.clone(),
+        }
+    }
+
+    /// The state after each marble up to `last_marble`. Each state is a copy,
+    /// so this is for watching a game rather than for playing a long one.
+    pub fn states(self, last_marble: Marble) -> States {
+        States {
+            game: self,
+            last_marble,
+        }
+    }
+}
+
+/// A snapshot of a `MarbleGame`, displayed like the puzzle's worked example:
+/// `[4]  0 (4) 2  1  3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameState {
+    pub player: Option<Player>,
+    pub current_marble: Option<Marble>,
+    pub circle: Vec<Marble>,
+    pub scores: HashMap<Player, Score>,
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.player {
+            Some(player) => write!(f, "[{}]", player)?,
+            None => write!(f, "[-]")?,
+        }
+        for &marble in self.circle.iter() {
+            if Some(marble) == self.current_marble {
+                write!(f, "({})", marble)?;
+            } else {
+                write!(f, " {} ", marble)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The iterator returned by `MarbleGame::states`.
+pub struct States {
+    game: MarbleGame,
+    last_marble: Marble,
+}
+
+impl Iterator for States {
+    type Item = GameState;
+
+    fn next(&mut self) -> Option<GameState> {
+        if self.game.next_marble > self.last_marble {
+            return None;
+        }
+        self.game.step();
+        Some(self.game.state())
+    }
+}
+
+mod parser {
+    use super::Game;
+    use combine::Parser;
+
+    use error::Result;
+    use parse::{self, located, tag, unsigned};
+
+    // 455 players; last marble is worth 71223 points
+    pub fn game<'a>() -> impl Parser<Input = parse::Stream<'a>, Output = Game> {
+        (
+            unsigned(),
+            tag(" players; last marble is worth "),
+            unsigned(),
+            tag(" points"),
+        )
+            .map(|t| Game {
+                players: t.0,
+                last_marble: t.2,
+            })
+    }
+
+    pub fn parse_games(input: &str) -> Result<Vec<Game>> {
+        parse::lines_of(input, located(game()))?
+            .into_iter()
+            .map(|game| {
+                if game.value.players == 0 {
+                    Err(game.error(input, "A game needs at least one player"))
+                } else {
+                    Ok(game.value)
+                }
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use combine::Parser;
+    use parse::{self, tag, unsigned};
+
+    #[test]
+    fn test_parse_games() {
+        assert_eq!(
+            parser::parse_games(
+                "10 players; last marble is worth 1618 points\n9 players; last marble is worth 25 points\n"
+            ).unwrap(),
+            vec![
+                Game {
+                    players: 10,
+                    last_marble: 1618
+                },
+                Game {
+                    players: 9,
+                    last_marble: 25
+                }
+            ]
+        );
+        assert_eq!(
+            parser::parse_games(
+                "10 players; last marble is worth 1618 points\n0 players; last marble is worth 1618 points"
+            ).unwrap_err()
+            .to_string(),
+            "parse error at line 2, column 1: A game needs at least one player"
+        );
+    }
 
     #[test]
     fn test_play() {
-        assert_eq!(::day_4::max_entry(&play(9, 25)), Some((5, 32)));
-        assert_eq!(::day_4::max_entry(&play(10, 1618)).map(|t| t.1), Some(8317));
-        assert_eq!(::day_4::max_entry(&play(13, 7999)).map(|t| t.1), Some(146373));
-        assert_eq!(::day_4::max_entry(&play(17, 1104)).map(|t| t.1), Some(2764));
-        assert_eq!(::day_4::max_entry(&play(21, 6111)).map(|t| t.1), Some(54718));
-        assert_eq!(::day_4::max_entry(&play(30, 5807)).map(|t| t.1), Some(37305));
+        // Each line is a game followed by `: high score is N`.
+        let examples = include_str!("../data/day_9_examples.txt");
+        let example = (parser::game(), tag(": high score is "), unsigned()).map(|t| (t.0, t.2));
+        let examples = parse::lines_of(examples, example).unwrap();
+        assert_eq!(examples.len(), 6);
+
+        for (game, high_score) in examples {
+            assert_eq!(
+                winner(&GameRules::new(game.players), game.last_marble).score,
+                high_score,
+                "{:?}",
+                game
+            );
+        }
+    }
+
+    #[test]
+    fn test_winner() {
+        assert_eq!(
+            winner(&GameRules::new(9), 25),
+            Winner {
+                player: Some(5),
+                score: 32
+            }
+        );
+        assert_eq!(
+            winner(&GameRules::new(9), 22),
+            Winner {
+                player: None,
+                score: 0
+            }
+        );
+
+        let games = Day9::parse("9 players; last marble is worth 25 points\n10 players; last marble is worth 1618 points").unwrap();
+        assert_eq!(
+            Day9::part_1(&games).unwrap().to_string(),
+            "32 (player 5), 8317 (player 10)"
+        );
+
+        // A game too short to score anything doesn't hide the others.
+        let games = Day9::parse(
+            "9 players; last marble is worth 22 points\n9 players; last marble is worth 25 points",
+        )
+        .unwrap();
+        assert_eq!(
+            Day9::part_1(&games).unwrap().to_string(),
+            "0 (no winner), 32 (player 5)"
+        );
+    }
+
+    #[test]
+    fn test_states() {
+        let states: Vec<String> = MarbleGame::new(GameRules::new(9))
+            .states(25)
+            .map(|state| state.to_string())
+            .collect();
+        assert_eq!(states[0], "[1] 0 (1)");
+        assert_eq!(states[3], "[4] 0 (4) 2  1  3 ");
+        assert_eq!(
+            states[22],
+            "[5] 0  16  8  17  4  18 (19) 2  20  10  21  5  22  11  1  12  6  13  3  14  7  15 "
+        );
+        assert_eq!(states.len(), 25);
+
+        let mut game = MarbleGame::new(GameRules::new(9));
+        assert_eq!(game.state().to_string(), "[-](0)");
+        for _ in 0..23 {
+            game.step();
+        }
+        assert_eq!(game.current_marble(), Some(19));
+        assert_eq!(game.scores().get(&5), Some(&32));
+    }
+
+    #[test]
+    fn test_rules() {
+        // Without scoring, every marble is placed.
+        let mut rules = GameRules::new(2);
+        rules.scoring_modulus = 0;
+        let mut game = MarbleGame::new(rules);
+        for _ in 0..30 {
+            game.step();
+        }
+        assert_eq!(game.circle().len(), 31);
+        assert!(game.scores().is_empty());
+
+        // Placing each marble straight after the current one counts up.
+        let mut rules = GameRules::new(2);
+        rules.insertion_offset = 1;
+        rules.scoring_modulus = 3;
+        rules.removal_offset = 1;
+        let state = MarbleGame::new(rules).states(4).last().unwrap();
+        assert_eq!(state.circle, vec![0, 2, 4]);
+        assert_eq!(state.scores.get(&1), Some(&4));
     }
 }
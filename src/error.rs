@@ -0,0 +1,138 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use combine::easy;
+use combine::stream::PointerOffset;
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: String,
+        error: io::Error,
+    },
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    NoSolution(String),
+    /// A day-specific option that doesn't apply, or has a bad value.
+    BadOption(String),
+    Panic(String),
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: &str, error: io::Error) -> Error {
+        Error::Io {
+            path: path.to_string(),
+            error,
+        }
+    }
+
+    /// A parse error at byte `offset` of `input`. Lines and columns count from
+    /// 1.
+    pub fn parse_at<M: Into<String>>(input: &str, offset: usize, message: M) -> Error {
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Error::Parse {
+            line,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    pub fn no_solution<M: Into<String>>(message: M) -> Error {
+        Error::NoSolution(message.into())
+    }
+
+    pub fn bad_option<M: Into<String>>(message: M) -> Error {
+        Error::BadOption(message.into())
+    }
+
+    /// Converts the errors from a combine `easy_parse` of `input`.
+    pub fn from_combine(input: &str, errors: easy::Errors<char, &str, PointerOffset>) -> Error {
+        struct Messages<'a>(&'a [easy::Error<char, &'a str>]);
+        impl<'a> fmt::Display for Messages<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                easy::Error::fmt_errors(self.0, f)
+            }
+        }
+
+        let offset = errors.position.translate_position(input);
+        let message = Messages(&errors.errors)
+            .to_string()
+            .lines()
+            .collect::<Vec<_>>()
+            .join("; ");
+        Error::parse_at(input, offset, message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {}", path, error),
+            Error::Parse {
+                line,
+                column,
+                ref message,
+            } => write!(
+                f,
+                "parse error at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::NoSolution(ref message) => write!(f, "no solution: {}", message),
+            Error::BadOption(ref message) => write!(f, "bad option: {}", message),
+            Error::Panic(ref message) => write!(f, "panicked: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use combine::parser::char::{digit, newline};
+    use combine::{many1, sep_by, Parser};
+
+    #[test]
+    fn test_parse_at() {
+        match Error::parse_at("ab\ncde\nf", 5, "oops") {
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => assert_eq!((line, column, message.as_ref()), (2, 3, "oops")),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_from_combine() {
+        let input = "12\n3x\n4";
+        let error = sep_by::<Vec<String>, _, _>(many1(digit()), newline())
+            .skip(combine::eof())
+            .easy_parse(input)
+            .map_err(|e| Error::from_combine(input, e))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "parse error at line 2, column 2: Unexpected `x`; Expected `lf newline` or `end of input`"
+        );
+    }
+}
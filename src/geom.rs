@@ -0,0 +1,526 @@
+//! Points, vectors and rectangles on an integer grid, shared by the grid
+//! puzzles.
+//!
+//! `x` grows to the right and `y` grows downwards, as in the puzzle texts.
+
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A signed integer type that can be used as a coordinate.
+pub trait Coord:
+    Copy
+    + Ord
+    + Hash
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    /// The value as a `usize`, or `None` if it's negative or too big.
+    fn to_usize(self) -> Option<usize>;
+}
+
+macro_rules! impl_coord {
+    ($($t:ty)*) => ($(
+        impl Coord for $t {
+            const ZERO: $t = 0;
+            const ONE: $t = 1;
+
+            fn abs(self) -> $t {
+                <$t>::abs(self)
+            }
+
+            fn checked_add(self, other: $t) -> Option<$t> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn checked_sub(self, other: $t) -> Option<$t> {
+                <$t>::checked_sub(self, other)
+            }
+
+            fn checked_mul(self, other: $t) -> Option<$t> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn to_usize(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+        }
+    )*)
+}
+
+impl_coord!(i8 i16 i32 i64 i128 isize);
+
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct Point<T = i32> {
+    pub x: T,
+    pub y: T,
+}
+
+/// The difference between two points.
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Vector<T = i32> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Coord> Point<T> {
+    pub fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+
+    pub fn origin() -> Point<T> {
+        Point::new(T::ZERO, T::ZERO)
+    }
+
+    /// `self + v`, or `None` if that's past the largest or smallest
+    /// coordinate.
+    pub fn checked_add(&self, v: Vector<T>) -> Option<Point<T>> {
+        Some(Point::new(self.x.checked_add(v.x)?, self.y.checked_add(v.y)?))
+    }
+
+    /// Sum of the distances along each axis.
+    pub fn manhattan_dist(&self, other: &Point<T>) -> T {
+        let d = *other - *self;
+        d.x.abs() + d.y.abs()
+    }
+
+    /// The larger of the distances along each axis, i.e. the number of king
+    /// moves between the points.
+    pub fn chebyshev_dist(&self, other: &Point<T>) -> T {
+        let d = *other - *self;
+        d.x.abs().max(d.y.abs())
+    }
+
+    /// The square of the straight-line distance, which orders points the same
+    /// way without leaving the integers.
+    pub fn euclidean_dist_squared(&self, other: &Point<T>) -> T {
+        let d = *other - *self;
+        d.x * d.x + d.y * d.y
+    }
+}
+
+impl<T: Coord> Vector<T> {
+    pub fn new(x: T, y: T) -> Vector<T> {
+        Vector { x, y }
+    }
+}
+
+impl<T: Coord> Add<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, v: Vector<T>) -> Point<T> {
+        Point::new(self.x + v.x, self.y + v.y)
+    }
+}
+
+impl<T: Coord> AddAssign<Vector<T>> for Point<T> {
+    fn add_assign(&mut self, v: Vector<T>) {
+        self.x += v.x;
+        self.y += v.y;
+    }
+}
+
+impl<T: Coord> Sub<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, v: Vector<T>) -> Point<T> {
+        Point::new(self.x - v.x, self.y - v.y)
+    }
+}
+
+impl<T: Coord> SubAssign<Vector<T>> for Point<T> {
+    fn sub_assign(&mut self, v: Vector<T>) {
+        self.x -= v.x;
+        self.y -= v.y;
+    }
+}
+
+impl<T: Coord> Sub for Point<T> {
+    type Output = Vector<T>;
+
+    fn sub(self, other: Point<T>) -> Vector<T> {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Coord> Add for Vector<T> {
+    type Output = Vector<T>;
+
+    fn add(self, other: Vector<T>) -> Vector<T> {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Coord> Sub for Vector<T> {
+    type Output = Vector<T>;
+
+    fn sub(self, other: Vector<T>) -> Vector<T> {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Coord> Neg for Vector<T> {
+    type Output = Vector<T>;
+
+    fn neg(self) -> Vector<T> {
+        Vector::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Coord> Mul<T> for Vector<T> {
+    type Output = Vector<T>;
+
+    fn mul(self, n: T) -> Vector<T> {
+        Vector::new(self.x * n, self.y * n)
+    }
+}
+
+/// The most squares of a grid to keep something for each of. The puzzles'
+/// grids are a few hundred to a few thousand squares across.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// An axis-aligned rectangle of grid squares, from `min` inclusive to `max`
+/// exclusive. It's empty if `max` isn't below and to the right of `min`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Rect<T = i32> {
+    pub min: Point<T>,
+    pub max: Point<T>,
+}
+
+impl<T: Coord> Rect<T> {
+    pub fn new(min: Point<T>, max: Point<T>) -> Rect<T> {
+        Rect { min, max }
+    }
+
+    /// The rectangle with its top left square at `min`.
+    pub fn with_size(min: Point<T>, width: T, height: T) -> Rect<T> {
+        Rect::new(min, min + Vector::new(width, height))
+    }
+
+    /// The rectangle with its top left square at `min`, or `None` if the size
+    /// is negative or the far edges are past the largest coordinate.
+    pub fn checked_with_size(min: Point<T>, width: T, height: T) -> Option<Rect<T>> {
+        if width < T::ZERO || height < T::ZERO {
+            return None;
+        }
+        let max = Point::new(min.x.checked_add(width)?, min.y.checked_add(height)?);
+        Some(Rect::new(min, max))
+    }
+
+    /// The smallest rectangle containing all of `points`, or `None` if there
+    /// aren't any.
+    pub fn bounding<'a, I>(points: I) -> Option<Rect<T>>
+    where
+        I: IntoIterator<Item = &'a Point<T>>,
+        T: 'a,
+    {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        });
+        Some(Rect::new(min, max + Vector::new(T::ONE, T::ONE)))
+    }
+
+    /// Like `bounding`, but also `None` if the rectangle's far edges or its
+    /// width or height are past the largest coordinate.
+    pub fn checked_bounding<'a, I>(points: I) -> Option<Rect<T>>
+    where
+        I: IntoIterator<Item = &'a Point<T>>,
+        T: 'a,
+    {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        });
+        Rect::new(min, max.checked_add(Vector::new(T::ONE, T::ONE))?).checked()
+    }
+
+    /// The rectangle, or `None` if its width or height is too big for `T`.
+    /// `width` and `height` can't overflow on a rectangle that passes.
+    pub fn checked(self) -> Option<Rect<T>> {
+        self.max.x.checked_sub(self.min.x)?;
+        self.max.y.checked_sub(self.min.y)?;
+        Some(self)
+    }
+
+    pub fn width(&self) -> T {
+        (self.max.x - self.min.x).max(T::ZERO)
+    }
+
+    pub fn height(&self) -> T {
+        (self.max.y - self.min.y).max(T::ZERO)
+    }
+
+    pub fn area(&self) -> T {
+        self.width() * self.height()
+    }
+
+    /// The area, or `None` if it's too big for `T`.
+    pub fn checked_area(&self) -> Option<T> {
+        self.width().checked_mul(self.height())
+    }
+
+    /// The number of squares, or `None` if there are more than `limit`.
+    pub fn checked_cells(&self, limit: usize) -> Option<usize> {
+        let width = self.max.x.checked_sub(self.min.x)?.max(T::ZERO);
+        let height = self.max.y.checked_sub(self.min.y)?.max(T::ZERO);
+        let cells = width.to_usize()?.checked_mul(height.to_usize()?)?;
+        if cells <= limit {
+            Some(cells)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() == T::ZERO || self.height() == T::ZERO
+    }
+
+    pub fn contains(&self, p: &Point<T>) -> bool {
+        self.min.x <= p.x && p.x < self.max.x && self.min.y <= p.y && p.y < self.max.y
+    }
+
+    /// Whether `p` is one of the outermost squares of the rectangle.
+    pub fn on_edge(&self, p: &Point<T>) -> bool {
+        self.contains(p)
+            && (p.x == self.min.x
+                || p.y == self.min.y
+                || p.x == self.max.x - T::ONE
+                || p.y == self.max.y - T::ONE)
+    }
+
+    /// Whether the rectangles share at least one square.
+    pub fn intersects(&self, other: &Rect<T>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The squares in both rectangles, or `None` if there aren't any.
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let rect = Rect::new(
+            Point::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            Point::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        );
+        if rect.is_empty() {
+            None
+        } else {
+            Some(rect)
+        }
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        if other.is_empty() {
+            return *self;
+        } else if self.is_empty() {
+            return *other;
+        }
+        Rect::new(
+            Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        )
+    }
+
+    /// The rectangle with `margin` more squares on every side.
+    pub fn expanded(&self, margin: T) -> Rect<T> {
+        let margin = Vector::new(margin, margin);
+        Rect::new(self.min - margin, self.max + margin)
+    }
+
+    /// Like `expanded`, but `None` if the edges or the size of the result are
+    /// past the largest coordinate.
+    pub fn checked_expanded(&self, margin: T) -> Option<Rect<T>> {
+        let margin = Vector::new(margin, margin);
+        Rect::new(self.min.checked_add(-margin)?, self.max.checked_add(margin)?).checked()
+    }
+
+    /// Every square in the rectangle, row by row.
+    pub fn points(&self) -> Points<T> {
+        Points {
+            rect: *self,
+            next: self.min,
+        }
+    }
+}
+
+/// The iterator returned by `Rect::points`.
+pub struct Points<T> {
+    rect: Rect<T>,
+    next: Point<T>,
+}
+
+impl<T: Coord> Iterator for Points<T> {
+    type Item = Point<T>;
+
+    fn next(&mut self) -> Option<Point<T>> {
+        if self.rect.is_empty() || self.next.y >= self.rect.max.y {
+            return None;
+        }
+        let p = self.next;
+        self.next.x += T::ONE;
+        if self.next.x >= self.rect.max.x {
+            self.next = Point::new(self.rect.min.x, self.next.y + T::ONE);
+        }
+        Some(p)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_point_arithmetic() {
+        let mut p = Point::new(1, 2);
+        let v = Vector::new(-3, 1);
+        assert_eq!(p + v, Point::new(-2, 3));
+        assert_eq!(p - v, Point::new(4, 1));
+        assert_eq!(p + v * 2 - p, Vector::new(-6, 2));
+        assert_eq!(-v, Vector::new(3, -1));
+        p += v;
+        assert_eq!(p, Point::new(-2, 3));
+        p -= v;
+        assert_eq!(p, Point::new(1, 2));
+    }
+
+    #[test]
+    fn test_distances() {
+        let p = Point::new(1i64, 1);
+        let q = Point::new(-2, 5);
+        assert_eq!(p.manhattan_dist(&q), 7);
+        assert_eq!(p.chebyshev_dist(&q), 4);
+        assert_eq!(p.euclidean_dist_squared(&q), 25);
+        assert_eq!(q.manhattan_dist(&p), 7);
+    }
+
+    #[test]
+    fn test_bounding() {
+        assert_eq!(Rect::<i32>::bounding(&[]), None);
+        let rect =
+            Rect::bounding(&[Point::new(3, -1), Point::new(-2, 4), Point::new(0, 0)]).unwrap();
+        assert_eq!(rect, Rect::new(Point::new(-2, -1), Point::new(4, 5)));
+        assert_eq!((rect.width(), rect.height(), rect.area()), (6, 6, 36));
+        assert!(rect.contains(&Point::new(3, 4)));
+        assert!(!rect.contains(&Point::new(4, 4)));
+        assert!(rect.on_edge(&Point::new(3, 0)));
+        assert!(!rect.on_edge(&Point::new(2, 0)));
+    }
+
+    #[test]
+    fn test_checked_bounding() {
+        let rect = Rect::checked_bounding(&[Point::new(3, -1), Point::new(-2, 4)]);
+        assert_eq!(rect, Some(Rect::new(Point::new(-2, -1), Point::new(4, 5))));
+        assert_eq!(Rect::<i32>::checked_bounding(&[]), None);
+        assert_eq!(Rect::checked_bounding(&[Point::new(i32::MAX, 0)]), None);
+        assert_eq!(
+            Rect::checked_bounding(&[Point::new(i32::MIN, 0), Point::new(i32::MAX - 1, 0)]),
+            None
+        );
+        assert_eq!(
+            Rect::checked_bounding(&[Point::new(i32::MAX - 1, 0)]),
+            Some(Rect::new(Point::new(i32::MAX - 1, 0), Point::new(i32::MAX, 1)))
+        );
+
+        let rect = Rect::with_size(Point::new(0, 0), 2, 2);
+        assert_eq!(rect.checked_expanded(1), Some(rect.expanded(1)));
+        assert_eq!(rect.checked_expanded(i32::MAX), None);
+        assert_eq!(
+            Rect::with_size(Point::new(i32::MAX - 5, 0), 1, 1).checked_expanded(10),
+            None
+        );
+        assert_eq!(Point::new(i32::MIN, 0).checked_add(Vector::new(-1, 0)), None);
+    }
+
+    #[test]
+    fn test_intersection_and_union() {
+        let a = Rect::with_size(Point::new(1, 3), 4, 4);
+        let b = Rect::with_size(Point::new(3, 1), 4, 4);
+        let c = Rect::with_size(Point::new(5, 5), 2, 2);
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect::with_size(Point::new(3, 3), 2, 2))
+        );
+        assert_eq!(a.intersection(&c), None);
+        assert!(b.intersects(&a));
+        assert!(!c.intersects(&a));
+        assert_eq!(a.union(&c), Rect::new(Point::new(1, 3), Point::new(7, 7)));
+        assert_eq!(c.expanded(1), Rect::new(Point::new(4, 4), Point::new(8, 8)));
+
+        let empty = Rect::with_size(Point::new(10, 10), 0, 5);
+        assert!(empty.is_empty());
+        assert_eq!(a.union(&empty), a);
+        assert!(!a.intersects(&empty));
+    }
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(
+            Rect::checked_with_size(Point::new(1, 2), 3, 0),
+            Some(Rect::new(Point::new(1, 2), Point::new(4, 2)))
+        );
+        assert_eq!(Rect::checked_with_size(Point::new(1, 2), -1, 3), None);
+        assert_eq!(
+            Rect::checked_with_size(Point::new(i32::MAX - 2, 0), 2, 1),
+            Some(Rect::new(
+                Point::new(i32::MAX - 2, 0),
+                Point::new(i32::MAX, 1)
+            ))
+        );
+        assert_eq!(Rect::checked_with_size(Point::new(0, i32::MAX), 1, 1), None);
+
+        let big = Rect::with_size(Point::new(0, 0), 50_000, 50_000);
+        assert_eq!(big.checked_area(), None);
+        assert_eq!(big.checked_cells(usize::MAX), Some(2_500_000_000));
+        assert_eq!(big.checked_cells(MAX_CELLS), None);
+        assert_eq!(
+            Rect::with_size(Point::new(0, 0), 3, 4).checked_cells(12),
+            Some(12)
+        );
+        assert_eq!(
+            Rect::with_size(Point::new(0, 0), 3, -4).checked_cells(12),
+            Some(0)
+        );
+        let wide = Rect::new(Point::new(i32::MIN, 0), Point::new(i32::MAX, 1));
+        assert_eq!(wide.checked_cells(usize::MAX), None);
+        assert_eq!(
+            Rect::with_size(Point::new(0i64, 0), 50_000, 50_000).checked_area(),
+            Some(2_500_000_000)
+        );
+    }
+
+    #[test]
+    fn test_points() {
+        assert_eq!(
+            Rect::with_size(Point::new(2, -1), 2, 2)
+                .points()
+                .collect::<Vec<_>>(),
+            vec![
+                Point::new(2, -1),
+                Point::new(3, -1),
+                Point::new(2, 0),
+                Point::new(3, 0)
+            ]
+        );
+        assert_eq!(Rect::with_size(Point::new(0, 0), 0, 3).points().count(), 0);
+    }
+}
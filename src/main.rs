@@ -1,24 +1,349 @@
+extern crate aoc;
+
+use aoc::bench::{self, Baseline};
+use aoc::render;
+use aoc::runner::{self, Input, Selection};
+use aoc::verify::{self, ExpectedAnswers};
+use aoc::Setting;
+
+const USAGE: &str = "USAGE: aoc [day] [data_file | - | --input <text>] [--format text | json]
+                [--render <image.ppm | image.png>]
+                [--animate <frame.ppm | frame.png> [--window <steps>]]
+                [--set <name>=<value>]... [--report]
+       aoc [all | first..last | first..=last] [--format table | json]
+       aoc verify [all | first..last | first..=last]
+       aoc bench [day | all | first..last | first..=last] [--iterations <n>]
+                 [--baseline <file>] [--save] [--threshold <percent>]
+       aoc list
+
+A data_file of - reads the puzzle input from stdin. --format json prints one
+JSON object per line for each part. --render draws days 3, 6 and 10 as a PPM
+image, or as a PNG if the file name ends in .png. --animate writes day 10 as
+numbered frames (frame_000.ppm, ...) from --window steps (default 10) before
+the message to --window steps after it.
+--report prints more about day 3 than the answers: which claims overlap, most
+conflicted first.
+
+--set changes how a day is solved. Day 6 takes metric=manhattan, chebyshev,
+euclidean or weighted:<x>,<y> (Manhattan with each axis's steps weighted), and
+search=bounds or everywhere (for a part 2 region beyond the coordinates).
+
+bench compares each median against the baseline file (bench_baseline.txt by
+default), flags any more than --threshold percent (default 10) slower, and
+with --save replaces the baseline with this run.";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// Removes `--format <format>` from wherever it appears in `args`.
+fn take_format(args: &mut Vec<String>) -> Format {
+    let i = match args.iter().position(|a| a == "--format") {
+        Some(i) => i,
+        None => return Format::Text,
+    };
+    if i + 1 >= args.len() {
+        usage_error("Missing format for --format");
+    }
+    let format = match args[i + 1].as_ref() {
+        "text" | "table" => Format::Text,
+        "json" => Format::Json,
+        f => usage_error(&format!("Unknown format: {:?}", f)),
+    };
+    args.drain(i..i + 2);
+    format
+}
+
+/// Removes `flag <value>` from wherever it appears in `args`.
+fn take_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == flag)?;
+    if i + 1 >= args.len() {
+        usage_error(&format!("Missing value for {}", flag));
+    }
+    let value = args[i + 1].clone();
+    args.drain(i..i + 2);
+    Some(value)
+}
+
+/// Removes `flag` from wherever it appears in `args`, returning whether it was
+/// there.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|a| a == flag) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes every `--set <name>=<value>`.
+fn take_settings(args: &mut Vec<String>) -> Vec<Setting> {
+    let mut settings = vec![];
+    while let Some(setting) = take_value(args, "--set") {
+        settings.push(setting.parse().unwrap_or_else(|e: String| usage_error(&e)));
+    }
+    settings
+}
+
+/// Where to draw a single day, if anywhere.
+struct Pictures {
+    render: Option<String>,
+    animate: Option<String>,
+    window: i64,
+}
+
+impl Pictures {
+    fn take(args: &mut Vec<String>) -> Pictures {
+        let render = take_value(args, "--render");
+        let animate = take_value(args, "--animate");
+        let window = take_value(args, "--window").map_or(10, |w| {
+            w.parse()
+                .ok()
+                .filter(|&w| w >= 0)
+                .unwrap_or_else(|| usage_error("--window must be a whole number of steps"))
+        });
+        Pictures {
+            render,
+            animate,
+            window,
+        }
+    }
+
+    fn any(&self) -> bool {
+        self.render.is_some() || self.animate.is_some()
+    }
+
+    /// Draws `input`, which is passed by text since each picture parses it
+    /// separately.
+    fn draw(
+        &self,
+        day: u32,
+        solver: &dyn aoc::Solver,
+        input: &str,
+        settings: &[Setting],
+    ) -> aoc::Result<()> {
+        let unsupported = |what| aoc::Error::no_solution(format!("day {} can't be {}", day, what));
+        if let Some(ref path) = self.render {
+            let image = solver
+                .render(input, settings)?
+                .ok_or_else(|| unsupported("rendered"))?;
+            image.save(path)?;
+        }
+        if let Some(ref path) = self.animate {
+            let frames = solver
+                .animate(input, settings, self.window)?
+                .ok_or_else(|| unsupported("animated"))?;
+            let paths = render::save_frames(frames, path)?;
+            if let (Some(first), Some(last)) = (paths.first(), paths.last()) {
+                eprintln!(
+                    "Wrote {} frames, {} to {}",
+                    paths.len(),
+                    first.display(),
+                    last.display()
+                );
+            }
+        }
+        Ok(())
+    }
+}
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let format = take_format(&mut args);
+    let pictures = Pictures::take(&mut args);
+    let settings = take_settings(&mut args);
+    let report = take_flag(&mut args, "--report");
 
     if args.len() < 2 {
-        panic!("USAGE: aoc [day] [data_file]")
+        usage_error("Missing day");
     }
 
-    let day = &args[1];
+    if let Err(e) = run(&args, format, &pictures, &settings, report) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(
+    args: &[String],
+    format: Format,
+    pictures: &Pictures,
+    settings: &[Setting],
+    report: bool,
+) -> aoc::Result<()> {
+    let registry = aoc::registry();
+
+    if args[1] == "list" {
+        for day in registry.days() {
+            println!("{}", day);
+        }
+        return Ok(());
+    }
+
+    if args[1] == "verify" {
+        let selection = match args.get(2) {
+            Some(arg) => arg.parse().unwrap_or_else(|e: String| usage_error(&e)),
+            None => Selection::All,
+        };
+        let expected = ExpectedAnswers::read(verify::ANSWERS_PATH)?;
+        let checks = verify::verify(&registry, &expected, &selection);
+        verify::print_report(&checks);
+        if !verify::all_passed(&checks) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if args[1] == "bench" {
+        return run_bench(&registry, &args[2..]);
+    }
+
+    let selection: Selection = args[1].parse().unwrap_or_else(|e: String| usage_error(&e));
+
+    let day = match selection {
+        Selection::Range(first, last) if first == last => first,
+        _ => {
+            if args.len() > 2 {
+                usage_error("Can only give input when running a single day");
+            }
+            if pictures.any() {
+                usage_error("Can only draw a single day");
+            }
+            if !settings.is_empty() {
+                usage_error("Can only --set options for a single day");
+            }
+            if report {
+                usage_error("Can only report on a single day");
+            }
+            let runs = runner::run_days(&registry, &selection);
+            match format {
+                Format::Text => runner::print_table(&runs),
+                Format::Json => runner::print_json(&runs),
+            }
+            if runs.iter().any(|run| run.failed()) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+    };
+
+    let mut input = match (args.get(2).map(|a| a.as_str()), args.get(3)) {
+        (None, _) => Input::File(runner::data_path(day)),
+        (Some("--input"), Some(text)) => Input::Inline(text.clone()),
+        (Some("--input"), None) => usage_error("Missing text for --input"),
+        (Some(arg), _) => Input::from_arg(arg),
+    };
+
+    let solver = match registry.get(day) {
+        Some(solver) => solver,
+        None => {
+            println!("I haven't solved that yet :(");
+            return Ok(());
+        }
+    };
+
+    if report && format == Format::Json {
+        usage_error("Can't --report with --format json");
+    }
 
-    match day.as_ref() {
-        "1" => aoc::day_1::run(&args[2]),
-        "2" => aoc::day_2::run(&args[2]),
-        "3" => aoc::day_3::run(&args[2]),
-        "4" => aoc::day_4::run(&args[2]),
-        "5" => aoc::day_5::run(&args[2]),
-        "6" => aoc::day_6::run(&args[2]),
-        "7" => aoc::day_7::run(&args[2]),
-        "8" => aoc::day_8::run(&args[2]),
-        "9" => aoc::day_9::run(),
-        "10" => aoc::day_10::run(&args[2]),
-        _ => println!("I haven't solved that yet :("),
+    if pictures.any() || report {
+        // Don't read stdin twice.
+        let text = input.read()?;
+        pictures.draw(day, solver, &text, settings)?;
+        if report {
+            let report = solver
+                .report(&text, settings)?
+                .ok_or_else(|| aoc::Error::no_solution(format!("day {} has no report", day)))?;
+            print!("{}", report);
+        }
+        input = Input::Inline(text);
+    }
+
+    let run = runner::run_day(day, solver, &input, settings);
+    if format == Format::Json {
+        let failed = run.failed();
+        runner::print_json(&[run]);
+        if failed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let answers = run.result?;
+    let mut failed = false;
+    for &(part, answer) in [(1, &answers.part_1), (2, &answers.part_2)].iter() {
+        match answer.value {
+            Ok(ref value) => println!("Day {}, part {}: {}", day, part, value),
+            Err(ref e) => {
+                eprintln!("Day {}, part {}: error: {}", day, part, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_bench(registry: &aoc::Registry, args: &[String]) -> aoc::Result<()> {
+    let mut selection = Selection::All;
+    let mut iterations = 10;
+    let mut baseline_path = bench::BASELINE_PATH.to_string();
+    let mut save = false;
+    let mut threshold = 10.0;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .unwrap_or_else(|| usage_error(&format!("Missing value for {}", arg)))
+        };
+        match arg.as_ref() {
+            "--iterations" => {
+                iterations = value()
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .unwrap_or_else(|| usage_error("--iterations must be a positive integer"))
+            }
+            "--baseline" => baseline_path = value(),
+            "--save" => save = true,
+            "--threshold" => {
+                threshold = value()
+                    .parse()
+                    .unwrap_or_else(|_| usage_error("--threshold must be a number"))
+            }
+            arg => selection = arg.parse().unwrap_or_else(|e: String| usage_error(&e)),
+        }
+    }
+
+    let mut baseline = Baseline::read(&baseline_path)?;
+    let mut slower = false;
+    for day in registry.days().filter(|&day| selection.contains(day)) {
+        let input = Input::File(runner::data_path(day)).read()?;
+        let result = bench::bench(day, registry.get(day).unwrap(), &input, iterations)?;
+        slower |= bench::print_report(&result, &baseline, threshold);
+        if save {
+            baseline.update(&result);
+        }
+    }
+
+    if save {
+        baseline.write(&baseline_path)?;
+        println!("Saved baseline to {}", baseline_path);
+    }
+    if slower {
+        std::process::exit(1);
     }
+    Ok(())
 }
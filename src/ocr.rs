@@ -0,0 +1,282 @@
+//! Reads the block-letter messages that some puzzles draw with `#` and `.`.
+
+use error::{Error, Result};
+
+struct Font {
+    height: usize,
+    /// Columns from the start of one letter to the start of the next.
+    pitch: usize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+/// The 6 by 10 letters used in 2018, two columns apart.
+const LARGE: Font = Font {
+    height: 10,
+    pitch: 8,
+    glyphs: &[
+        (
+            'A',
+            &[
+                "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'B',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+                "#....#", "#####.",
+            ],
+        ),
+        (
+            'C',
+            &[
+                ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+                "#....#", ".####.",
+            ],
+        ),
+        (
+            'E',
+            &[
+                "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+        (
+            'F',
+            &[
+                "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "#.....",
+            ],
+        ),
+        (
+            'G',
+            &[
+                ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+                "#...##", ".###.#",
+            ],
+        ),
+        (
+            'H',
+            &[
+                "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'J',
+            &[
+                "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+                "#...#.", ".###..",
+            ],
+        ),
+        (
+            'K',
+            &[
+                "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+                "#...#.", "#....#",
+            ],
+        ),
+        (
+            'L',
+            &[
+                "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+        (
+            'N',
+            &[
+                "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+                "#...##", "#....#",
+            ],
+        ),
+        (
+            'P',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "#.....",
+            ],
+        ),
+        (
+            'R',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'X',
+            &[
+                "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'Z',
+            &[
+                "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+    ],
+};
+
+/// The 4 by 6 letters used in later years, one column apart. `Y` is a column
+/// wider than the rest.
+const SMALL: Font = Font {
+    height: 6,
+    pitch: 5,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+fn is_lit(row: &[bool], x: usize) -> bool {
+    row.get(x).cloned().unwrap_or(false)
+}
+
+impl Font {
+    /// The letter drawn in the `pitch` columns of `rows` from `left`.
+    fn letter(&self, rows: &[Vec<bool>], left: usize) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|&&(_, glyph)| {
+                rows.iter().zip(glyph.iter()).all(|(row, glyph_row)| {
+                    let glyph_row: Vec<bool> = glyph_row.chars().map(|c| c == '#').collect();
+                    (0..self.pitch).all(|x| is_lit(row, left + x) == is_lit(&glyph_row, x))
+                })
+            })
+            .map(|&(c, _)| c)
+    }
+
+    /// Reads `rows` as letters `pitch` columns apart, starting `indent` columns
+    /// before the first column.
+    fn read(&self, rows: &[Vec<bool>], indent: usize) -> Option<String> {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let rows: Vec<Vec<bool>> = rows
+            .iter()
+            .map(|row| {
+                let mut indented = vec![false; indent];
+                indented.extend(row);
+                indented
+            })
+            .collect();
+        (0..width + indent)
+            .step_by(self.pitch)
+            .map(|left| self.letter(&rows, left))
+            .collect()
+    }
+}
+
+/// Reads a message drawn with `#` for lit cells, one line per row, in either
+/// of the fonts the puzzles use. Blank lines around it are ignored, and the
+/// drawing may be cropped to the lit cells.
+pub fn read(art: &str) -> Result<String> {
+    let rows: Vec<Vec<bool>> = art
+        .trim()
+        .lines()
+        .map(|line| line.trim().chars().map(|c| c == '#').collect())
+        .collect();
+
+    let font = match rows.len() {
+        10 => &LARGE,
+        6 => &SMALL,
+        0 => return Err(Error::no_solution("there's no message to read")),
+        n => {
+            return Err(Error::no_solution(format!(
+                "the message is {} rows tall, but only 6 and 10 row letters are known",
+                n
+            )))
+        }
+    };
+    assert_eq!(font.height, rows.len());
+
+    // If the drawing was cropped, the first letter may have lost blank columns
+    // from its left side, so try putting them back.
+    (0..font.pitch)
+        .filter_map(|indent| font.read(&rows, indent))
+        .next()
+        .ok_or_else(|| Error::no_solution(format!("can't read the message:\n{}", art.trim())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Draws `text` in `font`, cropped to the lit cells.
+    fn draw(font: &Font, text: &str) -> String {
+        let mut rows = vec![String::new(); font.height];
+        for c in text.chars() {
+            let glyph = font.glyphs.iter().find(|g| g.0 == c).unwrap().1;
+            for (row, glyph_row) in rows.iter_mut().zip(glyph.iter()) {
+                row.push_str(&format!("{:.<width$}", glyph_row, width = font.pitch));
+            }
+        }
+        let first = rows.iter().filter_map(|r| r.find('#')).min().unwrap();
+        let last = rows.iter().filter_map(|r| r.rfind('#')).max().unwrap();
+        rows.iter()
+            .map(|r| &r[first..last + 1])
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_read_large() {
+        let art = "
+######..#####...######..#....#..#..........###..######...####.
+#.......#....#.......#..#...#...#...........#........#..#....#
+#.......#....#.......#..#..#....#...........#........#..#.....
+#.......#....#......#...#.#.....#...........#.......#...#.....
+#####...#####......#....##......#...........#......#....#.....
+#.......#.........#.....##......#...........#.....#.....#..###
+#.......#........#......#.#.....#...........#....#......#....#
+#.......#.......#.......#..#....#.......#...#...#.......#....#
+#.......#.......#.......#...#...#.......#...#...#.......#...##
+#.......#.......######..#....#..######...###....######...###.#
+";
+        assert_eq!(read(art).unwrap(), "FPZKLJZG");
+        assert_eq!(read(&draw(&LARGE, "ABCEGHNRX")).unwrap(), "ABCEGHNRX");
+    }
+
+    #[test]
+    fn test_read_small() {
+        assert_eq!(read(&draw(&SMALL, "IYBOSU")).unwrap(), "IYBOSU");
+        assert_eq!(read(&draw(&SMALL, "JAFHYKLPRZ")).unwrap(), "JAFHYKLPRZ");
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert_eq!(
+            read("\n\n").unwrap_err().to_string(),
+            "no solution: there's no message to read"
+        );
+        assert_eq!(
+            read("#\n#\n#").unwrap_err().to_string(),
+            "no solution: the message is 3 rows tall, but only 6 and 10 row letters are known"
+        );
+        assert_eq!(
+            read("#..#\n####\n#..#\n#..#\n#..#\n#..#")
+                .unwrap_err()
+                .to_string(),
+            "no solution: can't read the message:\n#..#\n####\n#..#\n#..#\n#..#\n#..#"
+        );
+    }
+}
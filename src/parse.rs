@@ -0,0 +1,182 @@
+//! Parser combinators shared by every day's input parser, built on combine.
+//!
+//! A day's parser is usually `parse::lines_of(input, line())`, where `line`
+//! is made of `tag`s and `field`s around `unsigned` and `signed` numbers.
+//! Lines may end in spaces or `\r`, and the input may end in blank lines.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use combine::parser::char::{char, crlf, digit, newline, spaces};
+use combine::parser::range::{recognize, recognize_with_value, take_while, take_while1};
+use combine::{count_min_max, easy, eof, from_str, optional, sep_end_by, Parser};
+
+use error::{Error, Result};
+
+/// The input type of every shared parser.
+pub type Stream<'a> = easy::Stream<&'a str>;
+
+/// Runs `parser` on the whole of `input`, allowing trailing whitespace.
+pub fn parse_all<'a, P>(input: &'a str, parser: P) -> Result<P::Output>
+where
+    P: Parser<Input = Stream<'a>>,
+{
+    parser
+        .skip(spaces())
+        .skip(eof())
+        .easy_parse(input)
+        .map(|(output, _)| output)
+        .map_err(|e| Error::from_combine(input, e))
+}
+
+/// Runs `line` on each line of `input`.
+pub fn lines_of<'a, P>(input: &'a str, line: P) -> Result<Vec<P::Output>>
+where
+    P: Parser<Input = Stream<'a>>,
+{
+    parse_all(input, lines(line))
+}
+
+/// Zero or more `line`s, each ending in a newline except perhaps the last.
+pub fn lines<'a, P>(line: P) -> impl Parser<Input = Stream<'a>, Output = Vec<P::Output>>
+where
+    P: Parser<Input = Stream<'a>>,
+{
+    sep_end_by(line.skip(blanks()), newline().or(crlf()))
+}
+
+/// A parsed value and where it started in the input, for checks that can only
+/// be made once it's been parsed.
+#[derive(Debug)]
+pub struct Located<'a, T> {
+    /// The text the value was parsed from.
+    pub text: &'a str,
+    pub value: T,
+}
+
+impl<'a, T> Located<'a, T> {
+    /// A parse error pointing at the start of the value. `input` must be the
+    /// whole input that was parsed.
+    pub fn error<M: Into<String>>(&self, input: &str, message: M) -> Error {
+        let offset = self.text.as_ptr() as usize - input.as_ptr() as usize;
+        Error::parse_at(input, offset, message)
+    }
+}
+
+/// `p`, remembering where it started.
+pub fn located<'a, P>(p: P) -> impl Parser<Input = Stream<'a>, Output = Located<'a, P::Output>>
+where
+    P: Parser<Input = Stream<'a>>,
+{
+    recognize_with_value(p).map(|(text, value)| Located { text, value })
+}
+
+/// Spaces and tabs, but not newlines.
+pub fn blanks<'a>() -> impl Parser<Input = Stream<'a>, Output = &'a str> {
+    take_while(|c: char| c == ' ' || c == '\t')
+}
+
+/// `p` with any spaces or tabs around it, for fields that are padded to line
+/// up, like `position=< 9,  1>`.
+pub fn field<'a, P>(p: P) -> impl Parser<Input = Stream<'a>, Output = P::Output>
+where
+    P: Parser<Input = Stream<'a>>,
+{
+    blanks().with(p).skip(blanks())
+}
+
+/// Exactly the text `s`.
+pub fn tag<'a>(s: &'static str) -> impl Parser<Input = Stream<'a>, Output = &'a str> {
+    combine::parser::range::range(s)
+}
+
+/// One or more characters up to the next whitespace.
+pub fn word<'a>() -> impl Parser<Input = Stream<'a>, Output = &'a str> {
+    take_while1(|c: char| !c.is_whitespace())
+}
+
+/// An unsigned decimal integer of any type. Numbers too big for the type are
+/// errors rather than wrapping.
+pub fn unsigned<'a, T>() -> impl Parser<Input = Stream<'a>, Output = T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    from_str(take_while1(|c: char| c.is_ascii_digit())).expected("integer")
+}
+
+/// A decimal integer of any type with an optional `-` or `+` sign.
+pub fn signed<'a, T>() -> impl Parser<Input = Stream<'a>, Output = T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    from_str(recognize((
+        optional(char('-').or(char('+'))),
+        take_while1(|c: char| c.is_ascii_digit()),
+    )))
+    .expected("integer")
+}
+
+/// An unsigned decimal integer of exactly `n` digits, like the `05` in
+/// `00:05`.
+pub fn digits<'a, T>(n: usize) -> impl Parser<Input = Stream<'a>, Output = T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    from_str(count_min_max::<String, _>(n, n, digit()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(parse_all("123", unsigned::<u8>()).unwrap(), 123);
+        assert_eq!(parse_all("-12", signed::<i64>()).unwrap(), -12);
+        assert_eq!(parse_all("+12", signed::<i16>()).unwrap(), 12);
+        assert_eq!(parse_all("0042", digits::<u16>(4)).unwrap(), 42);
+        assert!(parse_all("-12", unsigned::<u32>()).is_err());
+        assert!(parse_all("042", digits::<u16>(4)).is_err());
+        assert_eq!(
+            parse_all("256", unsigned::<u8>()).unwrap_err().to_string(),
+            "parse error at line 1, column 1: number too large to fit in target type"
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let point =
+            || (field(signed::<i32>()), tag(","), field(signed::<i32>())).map(|t| (t.0, t.2));
+        assert_eq!(
+            lines_of("1,2\r\n -3,  4 \n5,6\n\n", point()).unwrap(),
+            vec![(1, 2), (-3, 4), (5, 6)]
+        );
+        assert_eq!(lines_of("", point()).unwrap(), vec![]);
+        assert_eq!(
+            lines_of("1,2\n3;4\n", point()).unwrap_err().to_string(),
+            "parse error at line 2, column 2: Unexpected `;`; Expected `,`"
+        );
+    }
+
+    #[test]
+    fn test_located() {
+        let input = "1\n-2\n";
+        let numbers = lines_of(input, located(signed::<i32>())).unwrap();
+        assert_eq!(numbers[1].value, -2);
+        assert_eq!(
+            numbers[1].error(input, "negative").to_string(),
+            "parse error at line 2, column 1: negative"
+        );
+    }
+
+    #[test]
+    fn test_word() {
+        assert_eq!(
+            lines_of("abc\nd-e  \n", word()).unwrap(),
+            vec!["abc", "d-e"]
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Raster images of the grid puzzles, written as PPM or PNG without any
+//! image libraries.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use error::{Error, Result};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    pub fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    /// The color `t` of the way from `self` to `other`, with `t` from 0 to 1.
+    pub fn lerp(self, other: Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+        Color::rgb(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+
+    /// A hue from 0 to 1 at the given saturation and value.
+    fn hsv(hue: f64, saturation: f64, value: f64) -> Color {
+        let h = hue.fract() * 6.0;
+        let c = value * saturation;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = value - c;
+        let byte = |v: f64| ((v + m) * 255.0).round() as u8;
+        Color::rgb(byte(r), byte(g), byte(b))
+    }
+}
+
+/// A color for region `index`. Neighbouring indexes get hues far apart, so
+/// regions numbered in input order are easy to tell apart.
+pub fn region_color(index: usize) -> Color {
+    // Stepping round the hue circle by the golden ratio never quite repeats.
+    Color::hsv(index as f64 * 0.618_033_988_75, 0.6, 0.95)
+}
+
+/// A color for a square covered `count` times, where the most any square is
+/// covered is `max`: black for none, blue for one, and orange through red for
+/// more.
+pub fn count_color(count: u32, max: u32) -> Color {
+    match count {
+        0 => Color::BLACK,
+        1 => Color::rgb(40, 70, 140),
+        _ if max <= 2 => Color::rgb(255, 150, 0),
+        _ => Color::rgb(255, 150, 0).lerp(
+            Color::rgb(220, 0, 0),
+            f64::from(count - 2) / f64::from(max - 2),
+        ),
+    }
+}
+
+/// The frames of an animation, each drawn only once it's asked for.
+pub type Frames = Box<dyn ExactSizeIterator<Item = Image>>;
+
+/// An RGB image, row by row from the top left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Color) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// An image with each pixel colored by `color(x, y)`.
+    pub fn from_fn<F>(width: usize, height: usize, mut color: F) -> Image
+    where
+        F: FnMut(usize, usize) -> Color,
+    {
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(color(x, y));
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Sets a pixel, ignoring any outside the image.
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// The image with each pixel blown up to a `factor` by `factor` square,
+    /// for grids too small to see.
+    pub fn scaled(&self, factor: usize) -> Image {
+        Image::from_fn(self.width * factor, self.height * factor, |x, y| {
+            self.pixels[y / factor * self.width + x / factor]
+        })
+    }
+
+    fn rgb_row(&self, y: usize) -> Vec<u8> {
+        self.pixels[y * self.width..(y + 1) * self.width]
+            .iter()
+            .flat_map(|c| vec![c.r, c.g, c.b])
+            .collect()
+    }
+
+    /// Writes a binary (`P6`) PPM.
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for y in 0..self.height {
+            w.write_all(&self.rgb_row(y))?;
+        }
+        Ok(())
+    }
+
+    /// Writes an uncompressed PNG.
+    pub fn write_png<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = vec![];
+        header.extend(&(self.width as u32).to_be_bytes());
+        header.extend(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, and the only compression, filter and
+        // interlace methods there are.
+        header.extend(&[8, 2, 0, 0, 0]);
+        write_png_chunk(w, b"IHDR", &header)?;
+
+        let mut scanlines = vec![];
+        for y in 0..self.height {
+            // Each row starts with its filter type, 0 for none.
+            scanlines.push(0);
+            scanlines.extend(self.rgb_row(y));
+        }
+        write_png_chunk(w, b"IDAT", &zlib_stored(&scanlines))?;
+        write_png_chunk(w, b"IEND", &[])
+    }
+
+    /// Saves the image as a PNG if `path` ends in `.png`, and as a PPM
+    /// otherwise.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let display = path.display().to_string();
+        let is_png = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+        File::create(path)
+            .and_then(|file| {
+                let mut w = BufWriter::new(file);
+                if is_png {
+                    self.write_png(&mut w)?;
+                } else {
+                    self.write_ppm(&mut w)?;
+                }
+                w.flush()
+            })
+            .map_err(|e| Error::io(&display, e))
+    }
+}
+
+/// Saves each frame as its own image, numbered from 0 after the stem of
+/// `path`: `out.ppm` becomes `out_000.ppm`, `out_001.ppm` and so on. Each is
+/// written as it comes, so only one is held at a time. Returns the paths
+/// written.
+pub fn save_frames<I, P>(frames: I, path: P) -> Result<Vec<PathBuf>>
+where
+    I: ExactSizeIterator<Item = Image>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_else(|| "ppm".as_ref());
+    let digits = frames.len().saturating_sub(1).to_string().len().max(3);
+    frames
+        .enumerate()
+        .map(|(i, frame)| {
+            let name = format!("{}_{:0width$}", stem, i, width = digits);
+            let frame_path = path.with_file_name(name).with_extension(extension);
+            frame.save(&frame_path)?;
+            Ok(frame_path)
+        })
+        .collect()
+}
+
+fn write_png_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    w.write_all(&crc.to_be_bytes())
+}
+
+/// `data` as a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+
+    // Deflate with a 32K window and no preset dictionary.
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(if last { 1 } else { 0 });
+        out.extend(&len.to_le_bytes());
+        out.extend(&(!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32<'a, I: IntoIterator<Item = &'a u8>>(bytes: I) -> u32 {
+    !bytes.into_iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + u32::from(byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn checkerboard() -> Image {
+        Image::from_fn(2, 2, |x, y| {
+            if (x + y) % 2 == 0 {
+                Color::WHITE
+            } else {
+                Color::rgb(1, 2, 3)
+            }
+        })
+    }
+
+    #[test]
+    fn test_ppm() {
+        let mut ppm = vec![];
+        checkerboard().write_ppm(&mut ppm).unwrap();
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend(&[255, 255, 255, 1, 2, 3, 1, 2, 3, 255, 255, 255]);
+        assert_eq!(ppm, expected);
+    }
+
+    #[test]
+    fn test_png() {
+        let mut png = vec![];
+        checkerboard().write_png(&mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        // The CRC of an empty IEND chunk is always the same.
+        assert_eq!(&png[png.len() - 4..], &[0xae, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        let big = vec![7; 70_000];
+        let stored = zlib_stored(&big);
+        // Two blocks, each with a five byte header.
+        assert_eq!(stored.len(), 2 + 5 + 5 + big.len() + 4);
+        assert_eq!(stored[2], 0);
+        assert_eq!(stored[2 + 5 + 0xffff], 1);
+    }
+
+    #[test]
+    fn test_scaled() {
+        let image = checkerboard().scaled(3);
+        assert_eq!((image.width, image.height), (6, 6));
+        assert_eq!(image.get(2, 2), Some(Color::WHITE));
+        assert_eq!(image.get(3, 2), Some(Color::rgb(1, 2, 3)));
+        assert_eq!(image.get(6, 0), None);
+    }
+
+    #[test]
+    fn test_save_frames() {
+        let dir = ::std::env::temp_dir().join(format!("aoc_frames_{}", ::std::process::id()));
+        ::std::fs::create_dir_all(&dir).unwrap();
+        let frames = vec![checkerboard(); 2];
+        let paths = save_frames(frames.into_iter(), dir.join("sky.png")).unwrap();
+        assert_eq!(
+            paths,
+            vec![dir.join("sky_000.png"), dir.join("sky_001.png")]
+        );
+        let png = ::std::fs::read(&paths[1]).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        ::std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_palettes() {
+        assert_ne!(region_color(0), region_color(1));
+        assert_eq!(count_color(0, 5), Color::BLACK);
+        assert_eq!(count_color(2, 5), Color::rgb(255, 150, 0));
+        assert_eq!(count_color(5, 5), Color::rgb(220, 0, 0));
+        assert_eq!(count_color(2, 2), Color::rgb(255, 150, 0));
+        assert_eq!(
+            Color::BLACK.lerp(Color::WHITE, 0.5),
+            Color::rgb(128, 128, 128)
+        );
+    }
+}
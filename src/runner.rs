@@ -0,0 +1,406 @@
+use std::any::Any;
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use error::{Error, Result};
+use solution::{Answers, Registry, Setting, Solver};
+
+/// Which days to run: `all`, a single day like `3`, or a range like `3..7`
+/// (exclusive) or `3..=7` (inclusive).
+#[derive(Debug, PartialEq)]
+pub enum Selection {
+    All,
+    Range(u32, u32),
+}
+
+impl Selection {
+    pub fn contains(&self, day: u32) -> bool {
+        match *self {
+            Selection::All => true,
+            Selection::Range(first, last) => first <= day && day <= last,
+        }
+    }
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> ::std::result::Result<Selection, String> {
+        let day = |d: &str| d.parse::<u32>().map_err(|_| format!("Not a day: {:?}", d));
+
+        if s == "all" {
+            Ok(Selection::All)
+        } else if let Some(i) = s.find("..=") {
+            Ok(Selection::Range(day(&s[..i])?, day(&s[i + 3..])?))
+        } else if let Some(i) = s.find("..") {
+            let end = day(&s[i + 2..])?;
+            if end == 0 {
+                return Err(format!("Empty range: {:?}", s));
+            }
+            Ok(Selection::Range(day(&s[..i])?, end - 1))
+        } else {
+            day(s).map(|d| Selection::Range(d, d))
+        }
+    }
+}
+
+/// The puzzle input for `day` that ships with the repo.
+pub fn data_path(day: u32) -> String {
+    format!("data/day_{}.txt", day)
+}
+
+/// Where to read a day's puzzle input from.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    File(String),
+    Stdin,
+    Inline(String),
+}
+
+impl Input {
+    /// `-` means stdin, anything else is a file path.
+    pub fn from_arg(arg: &str) -> Input {
+        if arg == "-" {
+            Input::Stdin
+        } else {
+            Input::File(arg.to_string())
+        }
+    }
+
+    pub fn read(&self) -> Result<String> {
+        match *self {
+            Input::File(ref path) => std::fs::read_to_string(path).map_err(|e| Error::io(path, e)),
+            Input::Stdin => {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(|e| Error::io("<stdin>", e))?;
+                Ok(input)
+            }
+            Input::Inline(ref input) => Ok(input.clone()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DayRun {
+    pub day: u32,
+    /// `input_hash` of the input, if it could be read.
+    pub input_hash: Option<String>,
+    pub result: Result<Answers>,
+    pub elapsed: Duration,
+}
+
+impl DayRun {
+    /// Whether the day, or either of its parts, failed.
+    pub fn failed(&self) -> bool {
+        match self.result {
+            Ok(ref answers) => answers.part_1.value.is_err() || answers.part_2.value.is_err(),
+            Err(_) => true,
+        }
+    }
+}
+
+/// A 64-bit FNV-1a hash of the puzzle input, as 16 hex digits. It only needs
+/// to tell inputs apart, and unlike `DefaultHasher` it's stable across Rust
+/// versions.
+pub fn input_hash(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn panic_error(payload: Box<dyn Any + Send>) -> Error {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        Error::Panic(s.to_string())
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        Error::Panic(s.clone())
+    } else {
+        Error::Panic("unknown cause".to_string())
+    }
+}
+
+/// Reads `input` and solves it with `options`. A panicking solution is a bug,
+/// but it's reported as an error rather than aborting the other days.
+pub fn run_day(day: u32, solver: &dyn Solver, input: &Input, options: &[Setting]) -> DayRun {
+    let start = Instant::now();
+    let mut hash = None;
+    let result = input.read().and_then(|input| {
+        hash = Some(input_hash(&input));
+        panic::catch_unwind(AssertUnwindSafe(|| solver.solve_with(&input, options)))
+            .unwrap_or_else(|p| Err(panic_error(p)))
+    });
+    DayRun {
+        day,
+        input_hash: hash,
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Runs every registered day in `selection` against its default data file.
+pub fn run_days(registry: &Registry, selection: &Selection) -> Vec<DayRun> {
+    // Failures are reported in the table, so don't also print each panic.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let runs = registry
+        .days()
+        .filter(|&day| selection.contains(day))
+        .map(|day| {
+            run_day(
+                day,
+                registry.get(day).unwrap(),
+                &Input::File(data_path(day)),
+                &[],
+            )
+        })
+        .collect();
+
+    panic::set_hook(hook);
+    runs
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.2?}", d)
+}
+
+/// Prints one row per part with its answer, or error, and time, or one error
+/// row per day that failed before either part ran.
+pub fn print_table(runs: &[DayRun]) {
+    let mut rows = vec![[
+        "day".to_string(),
+        "part".to_string(),
+        "answer".to_string(),
+        "time".to_string(),
+    ]];
+
+    for run in runs {
+        match run.result {
+            Ok(ref answers) => {
+                for (part, answer) in [(1, &answers.part_1), (2, &answers.part_2)].iter() {
+                    rows.push([
+                        run.day.to_string(),
+                        part.to_string(),
+                        match answer.value {
+                            Ok(ref value) => value.clone(),
+                            Err(ref e) => format!("error: {}", e),
+                        },
+                        format_duration(answer.elapsed),
+                    ]);
+                }
+            }
+            Err(ref e) => rows.push([
+                run.day.to_string(),
+                "-".to_string(),
+                format!("error: {}", e),
+                format_duration(run.elapsed),
+            ]),
+        }
+    }
+
+    let mut widths = [0; 4];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            // Multi-line answers, like a drawing, are as wide as their
+            // longest line.
+            let cell_width = cell.lines().map(|l| l.chars().count()).max();
+            *width = (*width).max(cell_width.unwrap_or(0));
+        }
+    }
+
+    for row in rows.iter() {
+        println!(
+            "{:>w0$}  {:>w1$}  {:<w2$}  {:>w3$}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        );
+    }
+
+    let total: Duration = runs.iter().map(|run| run.elapsed).sum();
+    let failed = runs.iter().filter(|run| run.failed()).count();
+    println!(
+        "{} days in {}, {} failed",
+        runs.len(),
+        format_duration(total),
+        failed
+    );
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub fn as_nanos(d: Duration) -> u64 {
+    d.as_secs() * 1_000_000_000 + u64::from(d.subsec_nanos())
+}
+
+/// One JSON object per part, with either an `answer` or an `error`, or one per
+/// day that failed before either part ran, for `--format json`.
+pub fn json_records(runs: &[DayRun]) -> Vec<String> {
+    let mut records = vec![];
+    for run in runs {
+        let input_hash = run
+            .input_hash
+            .as_ref()
+            .map(|h| json_string(h))
+            .unwrap_or_else(|| "null".to_string());
+        match run.result {
+            Ok(ref answers) => {
+                for &(part, answer) in [(1, &answers.part_1), (2, &answers.part_2)].iter() {
+                    let (key, value) = match answer.value {
+                        Ok(ref value) => ("answer", json_string(value)),
+                        Err(ref e) => ("error", json_string(&e.to_string())),
+                    };
+                    records.push(format!(
+                        "{{\"day\":{},\"part\":{},\"{}\":{},\"elapsed_ns\":{},\"input_hash\":{}}}",
+                        run.day,
+                        part,
+                        key,
+                        value,
+                        as_nanos(answer.elapsed),
+                        input_hash
+                    ));
+                }
+            }
+            Err(ref e) => records.push(format!(
+                "{{\"day\":{},\"error\":{},\"elapsed_ns\":{},\"input_hash\":{}}}",
+                run.day,
+                json_string(&e.to_string()),
+                as_nanos(run.elapsed),
+                input_hash
+            )),
+        }
+    }
+    records
+}
+
+/// Prints `json_records` as JSON Lines.
+pub fn print_json(runs: &[DayRun]) {
+    for record in json_records(runs) {
+        println!("{}", record);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!("all".parse(), Ok(Selection::All));
+        assert_eq!("4".parse(), Ok(Selection::Range(4, 4)));
+        assert_eq!("3..7".parse(), Ok(Selection::Range(3, 6)));
+        assert_eq!("3..=7".parse(), Ok(Selection::Range(3, 7)));
+        assert!("3..".parse::<Selection>().is_err());
+        assert!("x".parse::<Selection>().is_err());
+    }
+
+    #[test]
+    fn test_selection_contains() {
+        assert!(Selection::All.contains(25));
+        assert!(Selection::Range(3, 6).contains(3));
+        assert!(Selection::Range(3, 6).contains(6));
+        assert!(!Selection::Range(3, 6).contains(7));
+    }
+
+    #[test]
+    fn test_input_from_arg() {
+        assert_eq!(Input::from_arg("-"), Input::Stdin);
+        assert_eq!(
+            Input::from_arg("data/day_1.txt"),
+            Input::File("data/day_1.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_day_inline() {
+        let registry = ::registry();
+        let input = Input::Inline("+1\n-2\n+3\n+1\n".to_string());
+        let run = run_day(1, registry.get(1).unwrap(), &input, &[]);
+        assert_eq!(run.input_hash, Some(input_hash("+1\n-2\n+3\n+1\n")));
+        let answers = run.result.unwrap();
+        assert_eq!(answers.part_1.value.unwrap(), "3");
+        assert_eq!(answers.part_2.value.unwrap(), "2");
+    }
+
+    #[test]
+    fn test_one_part_fails() {
+        let registry = ::registry();
+        // Every claim overlaps another, so there's no part 2 answer.
+        let input = Input::Inline("#1 @ 0,0: 2x2\n#2 @ 1,1: 2x2\n".to_string());
+        let runs = vec![run_day(3, registry.get(3).unwrap(), &input, &[])];
+        assert!(runs[0].failed());
+        {
+            let answers = runs[0].result.as_ref().unwrap();
+            assert_eq!(answers.part_1.value.as_ref().unwrap(), "1");
+            assert!(answers.part_2.value.is_err());
+        }
+
+        let records = json_records(&runs);
+        assert_eq!(records.len(), 2);
+        assert!(records[0].starts_with("{\"day\":3,\"part\":1,\"answer\":\"1\","));
+        assert!(records[1].starts_with("{\"day\":3,\"part\":2,\"error\":"));
+    }
+
+    #[test]
+    fn test_input_hash() {
+        assert_eq!(input_hash(""), "cbf29ce484222325");
+        assert_eq!(input_hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_json_records() {
+        let registry = ::registry();
+        let runs = vec![run_day(
+            9,
+            registry.get(9).unwrap(),
+            &Input::Inline("x".to_string()),
+            &[],
+        )];
+        let records = json_records(&runs);
+        assert_eq!(records.len(), 1);
+        assert!(records[0].starts_with(
+            "{\"day\":9,\"error\":\"parse error at line 1, column 1: Unexpected `x`; Expected `integer` or ` players; last marble is worth `\",\"elapsed_ns\":"
+        ));
+        assert!(records[0].ends_with(&format!(",\"input_hash\":\"{}\"}}", input_hash("x"))));
+
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+
+    #[test]
+    fn test_run_day_missing_file() {
+        let registry = ::registry();
+        let input = Input::File("data/no_such_file.txt".to_string());
+        let run = run_day(1, registry.get(1).unwrap(), &input, &[]);
+        assert_eq!(run.input_hash, None);
+        assert!(run
+            .result
+            .unwrap_err()
+            .to_string()
+            .starts_with("data/no_such_file.txt: "));
+    }
+}
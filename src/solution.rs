@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use error::{Error, Result};
+use render::{Frames, Image};
+
+/// A day's puzzle: parse the input once, then answer both parts from it.
+pub trait Solution {
+    type Input;
+    type Part1: fmt::Display;
+    type Part2: fmt::Display;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+    fn part_1(input: &Self::Input) -> Result<Self::Part1>;
+    fn part_2(input: &Self::Input) -> Result<Self::Part2>;
+
+    /// A picture of the puzzle, for the days that have one.
+    fn render(_input: &Self::Input) -> Result<Option<Image>> {
+        Ok(None)
+    }
+
+    /// Frames of the puzzle over time, from `steps` before the moment that
+    /// matters to `steps` after it, for the days that have them.
+    fn animate(_input: &Self::Input, _steps: i64) -> Result<Option<Frames>> {
+        Ok(None)
+    }
+
+    /// A longer account of the puzzle than the answers, for the days that
+    /// have one.
+    fn report(_input: &Self::Input) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Changes how the parsed input is solved, for the days that can be
+    /// solved more than one way.
+    fn set_option(_input: &mut Self::Input, setting: &Setting) -> Result<()> {
+        Err(Error::bad_option(format!(
+            "unknown option {:?}",
+            setting.name
+        )))
+    }
+}
+
+/// A day-specific option, written `name=value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for Setting {
+    type Err = String;
+
+    fn from_str(s: &str) -> ::std::result::Result<Setting, String> {
+        match s.find('=') {
+            Some(i) if i > 0 => Ok(Setting {
+                name: s[..i].to_string(),
+                value: s[i + 1..].to_string(),
+            }),
+            _ => Err(format!("Expected name=value, got {:?}", s)),
+        }
+    }
+}
+
+/// Answer for a part that hasn't been solved yet.
+#[derive(Debug, PartialEq)]
+pub struct Unsolved;
+
+impl fmt::Display for Unsolved {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unsolved")
+    }
+}
+
+/// One part's answer, or why it couldn't be found, and how long it took.
+#[derive(Debug)]
+pub struct Answer {
+    pub value: Result<String>,
+    pub elapsed: Duration,
+}
+
+/// The answers to both parts. Each part can fail without hiding the other.
+#[derive(Debug)]
+pub struct Answers {
+    pub parse_time: Duration,
+    pub part_1: Answer,
+    pub part_2: Answer,
+}
+
+/// Object-safe view of a `Solution`, so that days with different input and
+/// answer types can live in the same `Registry`.
+pub trait Solver {
+    fn solve(&self, input: &str) -> Result<Answers> {
+        self.solve_with(input, &[])
+    }
+
+    fn solve_with(&self, input: &str, options: &[Setting]) -> Result<Answers>;
+    fn render(&self, input: &str, options: &[Setting]) -> Result<Option<Image>>;
+    fn animate(&self, input: &str, options: &[Setting], steps: i64) -> Result<Option<Frames>>;
+    fn report(&self, input: &str, options: &[Setting]) -> Result<Option<String>>;
+}
+
+fn timed<T, F: FnOnce() -> T>(f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+fn parse_with<S: Solution>(input: &str, options: &[Setting]) -> Result<S::Input> {
+    let mut input = S::parse(input)?;
+    set_options::<S>(&mut input, options)?;
+    Ok(input)
+}
+
+fn set_options<S: Solution>(input: &mut S::Input, options: &[Setting]) -> Result<()> {
+    for setting in options {
+        S::set_option(input, setting)?;
+    }
+    Ok(())
+}
+
+impl<S: Solution> Solver for S {
+    fn solve_with(&self, input: &str, options: &[Setting]) -> Result<Answers> {
+        let (input, parse_time) = timed(|| S::parse(input));
+        let mut input = input?;
+        set_options::<S>(&mut input, options)?;
+        let (part_1, part_1_time) = timed(|| S::part_1(&input).map(|a| a.to_string()));
+        let (part_2, part_2_time) = timed(|| S::part_2(&input).map(|a| a.to_string()));
+        Ok(Answers {
+            parse_time,
+            part_1: Answer {
+                value: part_1,
+                elapsed: part_1_time,
+            },
+            part_2: Answer {
+                value: part_2,
+                elapsed: part_2_time,
+            },
+        })
+    }
+
+    fn render(&self, input: &str, options: &[Setting]) -> Result<Option<Image>> {
+        S::render(&parse_with::<S>(input, options)?)
+    }
+
+    fn animate(&self, input: &str, options: &[Setting], steps: i64) -> Result<Option<Frames>> {
+        S::animate(&parse_with::<S>(input, options)?, steps)
+    }
+
+    fn report(&self, input: &str, options: &[Setting]) -> Result<Option<String>> {
+        S::report(&parse_with::<S>(input, options)?)
+    }
+}
+
+#[derive(Default)]
+pub struct Registry {
+    days: BTreeMap<u32, Box<dyn Solver>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry {
+            days: BTreeMap::new(),
+        }
+    }
+
+    pub fn register<S: Solution + 'static>(&mut self, day: u32, solution: S) {
+        self.days.insert(day, Box::new(solution));
+    }
+
+    pub fn get(&self, day: u32) -> Option<&dyn Solver> {
+        self.days.get(&day).map(|s| s.as_ref())
+    }
+
+    /// The registered days, in ascending order.
+    pub fn days<'a>(&'a self) -> impl Iterator<Item = u32> + 'a {
+        self.days.keys().cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Sum;
+
+    impl Solution for Sum {
+        type Input = Vec<u32>;
+        type Part1 = u32;
+        type Part2 = Unsolved;
+
+        fn parse(input: &str) -> Result<Vec<u32>> {
+            Ok(input.split(' ').map(|n| n.parse().unwrap()).collect())
+        }
+
+        fn part_1(input: &Vec<u32>) -> Result<u32> {
+            Ok(input.iter().sum())
+        }
+
+        fn part_2(_input: &Vec<u32>) -> Result<Unsolved> {
+            Ok(Unsolved)
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::new();
+        registry.register(3, Sum);
+        registry.register(1, Sum);
+
+        assert_eq!(registry.days().collect::<Vec<_>>(), vec![1, 3]);
+        assert!(registry.get(2).is_none());
+
+        let answers = registry.get(3).unwrap().solve("1 2 3").unwrap();
+        assert_eq!(answers.part_1.value.unwrap(), "6");
+        assert_eq!(answers.part_2.value.unwrap(), "unsolved");
+
+        let fast = "fast=yes".parse::<Setting>().unwrap();
+        assert_eq!(
+            registry
+                .get(3)
+                .unwrap()
+                .solve_with("1", &[fast])
+                .unwrap_err()
+                .to_string(),
+            "bad option: unknown option \"fast\""
+        );
+        assert!("=yes".parse::<Setting>().is_err());
+        assert_eq!("a=b=c".parse::<Setting>().unwrap().value, "b=c");
+    }
+}
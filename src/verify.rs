@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use error::{Error, Result};
+use runner::{self, Selection};
+use solution::Registry;
+
+/// Where `aoc verify` looks for the known-good answers.
+pub const ANSWERS_PATH: &str = "answers.toml";
+
+/// Known-good answers, keyed by day and part.
+///
+/// They're read from a small subset of TOML: a `[day_N]` table per day, with
+/// `part_1` and `part_2` keys whose values are strings or integers.
+///
+/// ```toml
+/// [day_1]
+/// part_1 = "479"
+/// part_2 = 66105
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct ExpectedAnswers(BTreeMap<(u32, u8), String>);
+
+impl ExpectedAnswers {
+    pub fn read(path: &str) -> Result<ExpectedAnswers> {
+        let input = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        ExpectedAnswers::parse(&input)
+    }
+
+    pub fn parse(input: &str) -> Result<ExpectedAnswers> {
+        let mut answers = BTreeMap::new();
+        let mut day = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let error = |column: usize, message: &str| Error::Parse {
+                line: i + 1,
+                column: column + 1,
+                message: message.to_string(),
+            };
+
+            let content = strip_comment(line);
+            let indent = content.len() - content.trim_start().len();
+            let content = content.trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            if content.starts_with('[') {
+                let table = content.trim_start_matches('[').trim_end_matches(']').trim();
+                if !content.ends_with(']') || !table.starts_with("day_") {
+                    return Err(error(indent, "Expected a table like [day_1]"));
+                }
+                day = Some(
+                    table["day_".len()..]
+                        .parse::<u32>()
+                        .map_err(|_| error(indent, "Expected a day number"))?,
+                );
+                continue;
+            }
+
+            let day = day.ok_or_else(|| error(indent, "Expected a [day_N] table first"))?;
+
+            let mut key_value = content.splitn(2, '=');
+            let key = key_value.next().unwrap_or("").trim();
+            let value = key_value
+                .next()
+                .ok_or_else(|| error(indent, "Expected `part_N = \"answer\"`"))?
+                .trim();
+
+            let part = match key {
+                "part_1" => 1,
+                "part_2" => 2,
+                _ => return Err(error(indent, "Expected part_1 or part_2")),
+            };
+
+            let value_column = value.as_ptr() as usize - line.as_ptr() as usize;
+            let value = if value.starts_with('"') {
+                if value.len() < 2 || !value.ends_with('"') {
+                    return Err(error(value_column, "Unterminated string"));
+                }
+                value[1..value.len() - 1].to_string()
+            } else if value.parse::<i64>().is_ok() {
+                value.to_string()
+            } else {
+                return Err(error(value_column, "Expected a string or an integer"));
+            };
+
+            if answers.insert((day, part), value).is_some() {
+                return Err(error(indent, "Duplicate answer"));
+            }
+        }
+
+        Ok(ExpectedAnswers(answers))
+    }
+
+    pub fn get(&self, day: u32, part: u8) -> Option<&str> {
+        self.0.get(&(day, part)).map(|s| s.as_str())
+    }
+}
+
+/// The line up to its first `#` outside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail { expected: String, actual: String },
+    Missing { actual: String },
+    Error(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Check {
+    pub day: u32,
+    pub part: u8,
+    pub status: Status,
+}
+
+fn check(expected: Option<&str>, actual: &str) -> Status {
+    match expected {
+        Some(expected) if expected == actual => Status::Pass,
+        Some(expected) => Status::Fail {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        },
+        None => Status::Missing {
+            actual: actual.to_string(),
+        },
+    }
+}
+
+/// Runs every day in `selection` against its default data file and compares
+/// the answers with `expected`.
+pub fn verify(
+    registry: &Registry,
+    expected: &ExpectedAnswers,
+    selection: &Selection,
+) -> Vec<Check> {
+    let mut checks = vec![];
+    for run in runner::run_days(registry, selection) {
+        match run.result {
+            Ok(answers) => {
+                for &(part, answer) in [(1, &answers.part_1), (2, &answers.part_2)].iter() {
+                    checks.push(Check {
+                        day: run.day,
+                        part,
+                        status: match answer.value {
+                            Ok(ref value) => check(expected.get(run.day, part), value),
+                            Err(ref e) => Status::Error(e.to_string()),
+                        },
+                    });
+                }
+            }
+            Err(e) => {
+                for &part in [1, 2].iter() {
+                    checks.push(Check {
+                        day: run.day,
+                        part,
+                        status: Status::Error(e.to_string()),
+                    });
+                }
+            }
+        }
+    }
+    checks
+}
+
+/// Whether every check passed or has no expected answer yet.
+pub fn all_passed(checks: &[Check]) -> bool {
+    checks.iter().all(|c| match c.status {
+        Status::Pass | Status::Missing { .. } => true,
+        Status::Fail { .. } | Status::Error(_) => false,
+    })
+}
+
+/// Prints a PASS/FAIL/MISSING line per part, with the expected and actual
+/// answers under each failure.
+pub fn print_report(checks: &[Check]) {
+    for c in checks {
+        let label = format!("day {:>2}, part {}", c.day, c.part);
+        match c.status {
+            Status::Pass => println!("PASS     {}", label),
+            Status::Fail {
+                ref expected,
+                ref actual,
+            } => {
+                println!("FAIL     {}", label);
+                println!("    - expected: {}", expected);
+                println!("    + actual:   {}", actual);
+            }
+            Status::Missing { ref actual } => {
+                println!("MISSING  {}: got {}", label, actual)
+            }
+            Status::Error(ref e) => println!("FAIL     {}: {}", label, e),
+        }
+    }
+
+    let count = |f: fn(&Status) -> bool| checks.iter().filter(|c| f(&c.status)).count();
+    println!(
+        "{} passed, {} failed, {} missing",
+        count(|s| *s == Status::Pass),
+        count(|s| matches!(*s, Status::Fail { .. } | Status::Error(_))),
+        count(|s| matches!(*s, Status::Missing { .. })),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_answers() {
+        let answers = ExpectedAnswers::parse(
+            "# Answers\n\n[day_1]\npart_1 = \"479\"\npart_2 = 66105 # comment\n\n[day_7]\npart_1 = \"HEGM\"\n",
+        ).unwrap();
+        assert_eq!(answers.get(1, 1), Some("479"));
+        assert_eq!(answers.get(1, 2), Some("66105"));
+        assert_eq!(answers.get(7, 1), Some("HEGM"));
+        assert_eq!(answers.get(7, 2), None);
+
+        let answers =
+            ExpectedAnswers::parse("[day_3]\npart_1 = \"#..#\" # a # comment\npart_2 = \"#\"#\"\n")
+                .unwrap();
+        assert_eq!(answers.get(3, 1), Some("#..#"));
+        assert_eq!(answers.get(3, 2), Some("#"));
+    }
+
+    #[test]
+    fn test_parse_answers_errors() {
+        let error = |input| ExpectedAnswers::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("part_1 = \"1\""),
+            "parse error at line 1, column 1: Expected a [day_N] table first"
+        );
+        assert_eq!(
+            error("[day_1]\npart_3 = \"1\""),
+            "parse error at line 2, column 1: Expected part_1 or part_2"
+        );
+        assert_eq!(
+            error("[day_1]\npart_1 = one"),
+            "parse error at line 2, column 10: Expected a string or an integer"
+        );
+        assert_eq!(
+            error("[day_1]\npart_1 = \"#..# # comment"),
+            "parse error at line 2, column 10: Unterminated string"
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(Some("1"), "1"), Status::Pass);
+        assert_eq!(
+            check(Some("1"), "2"),
+            Status::Fail {
+                expected: "1".to_string(),
+                actual: "2".to_string()
+            }
+        );
+        assert_eq!(
+            check(None, "2"),
+            Status::Missing {
+                actual: "2".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_answers_file() {
+        ExpectedAnswers::read(ANSWERS_PATH).unwrap();
+    }
+}